@@ -25,11 +25,11 @@ fn build_completion_manpage() -> eyre::Result<()> {
 
     // completion
     for &shell in Shell::value_variants() {
-        clap_complete::generate_to(shell, &mut cmd, "bandwhich", &gen_dir)?;
+        clap_complete::generate_to(shell, &mut cmd, "nethugs", &gen_dir)?;
     }
 
     // manpage
-    let mut manpage_out = File::create(gen_dir.join("bandwhich.1"))?;
+    let mut manpage_out = File::create(gen_dir.join("nethugs.1"))?;
     let manpage = Man::new(cmd);
     manpage.render(&mut manpage_out)?;
 
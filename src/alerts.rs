@@ -0,0 +1,467 @@
+use std::{collections::HashMap, fmt, process, thread, time::Duration};
+
+use chrono::{DateTime, Local, NaiveDate};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cli::parse_age,
+    display::{BandwidthUnitFamily, DisplayBandwidth, NetworkData},
+};
+
+/// How long a webhook may take to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which way the traffic of a rule flows.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    Download,
+    Upload,
+    #[default]
+    Both,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Download => "download",
+            Direction::Upload => "upload",
+            Direction::Both => "traffic",
+        }
+    }
+
+    fn bytes(self, data: &NetworkData) -> u128 {
+        match self {
+            Direction::Download => data.total_bytes_downloaded,
+            Direction::Upload => data.total_bytes_uploaded,
+            Direction::Both => data.total_bytes_downloaded + data.total_bytes_uploaded,
+        }
+    }
+}
+
+/// What makes a rule fire.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// The rate stays above `bytes_per_second` for at least `sustained`.
+    Rate {
+        bytes_per_second: u64,
+        sustained: Duration,
+    },
+    /// The traffic since midnight goes above `bytes`. Without `--record`, only the traffic
+    /// seen since nethugs started counts.
+    Today { bytes: u64 },
+}
+
+/// Which condition of a rule an event is about.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertKind {
+    Rate,
+    Today,
+}
+
+/// A rule of the `[[alerts]]` tables of the config file.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "RuleConfig")]
+pub struct AlertRule {
+    pub name: String,
+    /// The process name the rule watches, adding up the traffic of every process of that
+    /// name, or all traffic if not given.
+    pub process: Option<String>,
+    pub direction: Direction,
+    pub condition: Condition,
+    /// A shell command run when the rule fires, with the event as JSON in `NETHUGS_ALERT`.
+    pub command: Option<String>,
+    /// A URL the event is posted to as JSON when the rule fires.
+    pub webhook: Option<String>,
+}
+
+/// An alert rule as written in the config file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    name: String,
+    process: Option<String>,
+    #[serde(default)]
+    direction: Direction,
+    rate: Option<String>,
+    #[serde(rename = "for")]
+    sustained: Option<String>,
+    today: Option<String>,
+    command: Option<String>,
+    webhook: Option<String>,
+}
+
+impl TryFrom<RuleConfig> for AlertRule {
+    type Error = String;
+
+    fn try_from(rule: RuleConfig) -> Result<Self, Self::Error> {
+        let condition = match (rule.rate, rule.sustained, rule.today) {
+            (Some(rate), sustained, None) => Condition::Rate {
+                bytes_per_second: parse_bytes(rate.trim_end_matches("/s"))?,
+                sustained: sustained
+                    .as_deref()
+                    .map(parse_age)
+                    .transpose()?
+                    .unwrap_or_default(),
+            },
+            (None, None, Some(today)) => Condition::Today {
+                bytes: parse_bytes(&today)?,
+            },
+            (None, Some(_), _) => {
+                return Err(format!("alert \"{}\": `for` needs a `rate`", rule.name))
+            }
+            _ => {
+                return Err(format!(
+                    "alert \"{}\" needs either a `rate` or a `today` limit",
+                    rule.name
+                ))
+            }
+        };
+        Ok(AlertRule {
+            name: rule.name,
+            process: rule.process,
+            direction: rule.direction,
+            condition,
+            command: rule.command,
+            webhook: rule.webhook,
+        })
+    }
+}
+
+/// Parses an amount of bytes such as `512`, `500KB` or `5MiB`.
+fn parse_bytes(bytes: &str) -> Result<u64, String> {
+    let split = bytes
+        .find(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+        .unwrap_or(bytes.len());
+    let (number, unit) = bytes.split_at(split);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("invalid amount of bytes: {bytes}"))?;
+    let unit = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1000,
+        "MB" => 1000_u64.pow(2),
+        "GB" => 1000_u64.pow(3),
+        "TB" => 1000_u64.pow(4),
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return Err(format!("invalid amount of bytes: {bytes}")),
+    };
+    Ok((number * unit as f64) as u64)
+}
+
+/// A rule firing, as logged and sent to hooks.
+#[derive(Clone, Debug, Serialize)]
+pub struct AlertEvent {
+    pub rule: String,
+    pub kind: AlertKind,
+    pub process: Option<String>,
+    pub direction: Direction,
+    /// The rate in bytes per second, or the bytes of the day, that went above the limit.
+    pub value: u128,
+    pub limit: u64,
+    pub at: DateTime<Local>,
+}
+
+impl fmt::Display for AlertEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = |bytes: f64| DisplayBandwidth {
+            bandwidth: bytes,
+            unit_family: BandwidthUnitFamily::default(),
+        };
+        let unit = match self.kind {
+            AlertKind::Rate => "/s",
+            AlertKind::Today => " today",
+        };
+        write!(
+            f,
+            "\"{}\": {} {} at {}{unit}, above {}{unit}",
+            self.rule,
+            self.process.as_deref().unwrap_or("total"),
+            self.direction.as_str(),
+            bytes(self.value as f64),
+            bytes(self.limit as f64),
+        )
+    }
+}
+
+/// A rule along with the traffic it has seen.
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub rule: AlertRule,
+    /// How long the rate has been above the limit, in seconds.
+    above_for: f64,
+    /// The day the traffic of `Today` rules is counted for.
+    day: Option<NaiveDate>,
+    today_bytes: u128,
+    /// Whether the rule fired, and has not recovered since.
+    pub firing: bool,
+}
+
+impl Alert {
+    pub fn new(rule: AlertRule) -> Self {
+        Alert {
+            rule,
+            above_for: 0.0,
+            day: None,
+            today_bytes: 0,
+            firing: false,
+        }
+    }
+
+    /// Starts the count of a `Today` rule from the traffic recorded earlier on `day`, by
+    /// process name, so that it does not start over when nethugs is restarted.
+    pub fn seed_today(&mut self, day: NaiveDate, recorded: &HashMap<String, NetworkData>) {
+        if !matches!(self.rule.condition, Condition::Today { .. }) {
+            return;
+        }
+        let direction = self.rule.direction;
+        self.day = Some(day);
+        self.today_bytes = recorded
+            .iter()
+            .filter(|(name, _)| {
+                self.rule
+                    .process
+                    .as_ref()
+                    .is_none_or(|process| process == *name)
+            })
+            .map(|(_, data)| direction.bytes(data))
+            .sum();
+    }
+
+    /// Accounts the traffic of a tick lasting `seconds`, returning an event if the rule
+    /// fires. A rule fires once, until its rate drops back or a new day begins.
+    pub fn evaluate(
+        &mut self,
+        at: DateTime<Local>,
+        seconds: f64,
+        data: &NetworkData,
+    ) -> Option<AlertEvent> {
+        let bytes = self.rule.direction.bytes(data);
+        let (kind, value, limit) = match self.rule.condition {
+            Condition::Rate {
+                bytes_per_second,
+                sustained,
+            } => {
+                let rate = (bytes as f64 / seconds).round() as u128;
+                if rate <= bytes_per_second as u128 {
+                    if self.firing {
+                        info!("Alert \"{}\" recovered", self.rule.name);
+                    }
+                    self.above_for = 0.0;
+                    self.firing = false;
+                    return None;
+                }
+                self.above_for += seconds;
+                if self.above_for < sustained.as_secs_f64() {
+                    return None;
+                }
+                (AlertKind::Rate, rate, bytes_per_second)
+            }
+            Condition::Today { bytes: limit } => {
+                let today = at.date_naive();
+                if self.day != Some(today) {
+                    self.day = Some(today);
+                    self.today_bytes = 0;
+                    self.firing = false;
+                }
+                self.today_bytes += bytes;
+                if self.today_bytes <= limit as u128 {
+                    return None;
+                }
+                (AlertKind::Today, self.today_bytes, limit)
+            }
+        };
+        if self.firing {
+            return None;
+        }
+        self.firing = true;
+        Some(AlertEvent {
+            rule: self.rule.name.clone(),
+            kind,
+            process: self.rule.process.clone(),
+            direction: self.rule.direction,
+            value,
+            limit,
+            at,
+        })
+    }
+}
+
+/// Runs the command and calls the webhook of a rule that fired, in the background.
+pub fn dispatch(rule: &AlertRule, event: &AlertEvent) {
+    if rule.command.is_none() && rule.webhook.is_none() {
+        return;
+    }
+    let json = match serde_json::to_string(event) {
+        Ok(json) => json,
+        Err(err) => return warn!("Cannot encode alert {event}: {err}"),
+    };
+    let command = rule.command.clone();
+    let webhook = rule.webhook.clone();
+    let name = rule.name.clone();
+    let spawned = thread::Builder::new()
+        .name("alert_hooks".to_string())
+        .spawn(move || {
+            if let Some(command) = command {
+                run_command(&name, &command, &json);
+            }
+            if let Some(webhook) = webhook {
+                let agent = ureq::Agent::config_builder()
+                    .timeout_global(Some(WEBHOOK_TIMEOUT))
+                    .build()
+                    .new_agent();
+                let response = agent
+                    .post(&webhook)
+                    .header("Content-Type", "application/json")
+                    .send(&json);
+                if let Err(err) = response {
+                    warn!("Webhook of alert \"{name}\" failed: {err}");
+                }
+            }
+        });
+    if let Err(err) = spawned {
+        warn!("Cannot run the hooks of alert \"{}\": {err}", rule.name);
+    }
+}
+
+fn run_command(name: &str, command: &str, json: &str) {
+    #[cfg(not(target_os = "windows"))]
+    let mut shell = process::Command::new("sh");
+    #[cfg(not(target_os = "windows"))]
+    shell.arg("-c");
+    #[cfg(target_os = "windows")]
+    let mut shell = process::Command::new("cmd");
+    #[cfg(target_os = "windows")]
+    shell.arg("/C");
+
+    let status = shell
+        .arg(command)
+        .env("NETHUGS_ALERT", json)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => (),
+        Ok(status) => warn!("Command of alert \"{name}\" failed: {status}"),
+        Err(err) => warn!("Cannot run the command of alert \"{name}\": {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn rule(toml: &str) -> Result<AlertRule, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    #[test]
+    fn parse_rules() {
+        let rsync = rule(
+            r#"
+                name = "rsync upload"
+                process = "rsync"
+                direction = "upload"
+                rate = "5MiB/s"
+                for = "30s"
+                command = "true"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            rsync.condition,
+            Condition::Rate {
+                bytes_per_second: 5 << 20,
+                sustained: Duration::from_secs(30),
+            }
+        );
+        let daily = rule("name = 'daily'\ndirection = 'download'\ntoday = '10GB'").unwrap();
+        assert_eq!(
+            daily.condition,
+            Condition::Today {
+                bytes: 10_000_000_000
+            }
+        );
+
+        assert!(rule("name = 'both'\nrate = '1MiB'\ntoday = '1GiB'").is_err());
+        assert!(rule("name = 'none'").is_err());
+        assert!(rule("name = 'unit'\nrate = '5 parsecs'").is_err());
+    }
+
+    #[test]
+    fn fire_once_until_recovered() {
+        let mut alert = Alert::new(
+            rule("name = 'up'\ndirection = 'upload'\nrate = '1000'\nfor = '2s'").unwrap(),
+        );
+        let at = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let upload = |bytes| NetworkData {
+            total_bytes_downloaded: 0,
+            total_bytes_uploaded: bytes,
+        };
+
+        assert!(alert.evaluate(at, 1.0, &upload(5000)).is_none());
+        let event = alert.evaluate(at, 1.0, &upload(5000)).unwrap();
+        assert_eq!((event.value, event.limit), (5000, 1000));
+        assert!(alert.evaluate(at, 1.0, &upload(5000)).is_none());
+        assert!(alert.evaluate(at, 1.0, &upload(10)).is_none());
+        assert!(!alert.firing);
+
+        let mut alert = Alert::new(rule("name = 'daily'\ntoday = '1KB'").unwrap());
+        assert!(alert.evaluate(at, 1.0, &upload(600)).is_none());
+        assert!(alert.evaluate(at, 1.0, &upload(600)).is_some());
+        assert!(alert.evaluate(at, 1.0, &upload(600)).is_none());
+        // the count starts over the next day
+        let tomorrow = at + chrono::Duration::days(1);
+        assert!(alert.evaluate(tomorrow, 1.0, &upload(600)).is_none());
+    }
+
+    #[test]
+    fn seed_today_from_recorded_usage() {
+        let at = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let recorded = HashMap::from([
+            (
+                "curl".to_string(),
+                NetworkData {
+                    total_bytes_downloaded: 700,
+                    total_bytes_uploaded: 5,
+                },
+            ),
+            (
+                "ssh".to_string(),
+                NetworkData {
+                    total_bytes_downloaded: 200,
+                    total_bytes_uploaded: 5,
+                },
+            ),
+        ]);
+        let download = NetworkData {
+            total_bytes_downloaded: 200,
+            total_bytes_uploaded: 0,
+        };
+
+        let mut alert = Alert::new(
+            rule("name = 'curl'\nprocess = 'curl'\ndirection = 'download'\ntoday = '1KB'").unwrap(),
+        );
+        alert.seed_today(at.date_naive(), &recorded);
+        assert!(alert.evaluate(at, 1.0, &download).is_none());
+        let event = alert.evaluate(at, 1.0, &download).unwrap();
+        assert_eq!((event.kind, event.value), (AlertKind::Today, 1100));
+
+        let mut alert = Alert::new(rule("name = 'all'\ntoday = '1KB'").unwrap());
+        alert.seed_today(at.date_naive(), &recorded);
+        assert!(alert.evaluate(at, 1.0, &download).is_some());
+        // what was recorded on another day does not count
+        let mut alert = Alert::new(rule("name = 'all'\ntoday = '1KB'").unwrap());
+        alert.seed_today(at.date_naive(), &recorded);
+        let tomorrow = at + chrono::Duration::days(1);
+        assert!(alert.evaluate(tomorrow, 1.0, &download).is_none());
+    }
+}
@@ -1,25 +1,52 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
-use clap::{Args, Parser, ValueEnum, ValueHint};
+use clap::{Args, Parser, Subcommand, ValueEnum, ValueHint};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
 use derive_more::Debug;
 use strum::EnumIter;
 
 #[derive(Clone, Debug, Parser, Default)]
-#[command(name = "bandwhich", version)]
+#[command(name = "nethugs", version)]
 pub struct Opt {
     #[arg(short, long)]
     /// The network interface to listen on, eg. eth0
     pub interface: Option<String>,
 
+    #[arg(long, conflicts_with = "all_netns")]
+    /// Capture and resolve sockets in another network namespace, given by name or PID (Linux only)
+    pub netns: Option<String>,
+
+    #[arg(long)]
+    /// Resolve sockets in all network namespaces, labelling rows with their namespace (Linux only)
+    pub all_netns: bool,
+
     #[arg(short, long)]
     /// Machine friendlier output
     pub raw: bool,
 
+    #[arg(short, long)]
+    /// Do not attempt to resolve IPs to their hostnames, which is implied by --raw
+    pub no_resolve: bool,
+
+    #[arg(long)]
+    /// Only show traffic of sockets owned by the invoking user (Linux only)
+    pub only_mine: bool,
+
     #[arg(long, value_hint = ValueHint::FilePath)]
     /// Enable debug logging to a file
     pub log_to: Option<PathBuf>,
 
+    #[arg(long)]
+    /// Record the traffic of each process and systemd unit to the usage database, for `report`
+    pub record: bool,
+
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    /// The usage database [default: nethugs/usage.sqlite in the data directory]
+    pub db: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[command(flatten)]
     pub verbosity: Verbosity<InfoLevel>,
 
@@ -27,11 +54,153 @@ pub struct Opt {
     pub render_opts: RenderOpts,
 }
 
-#[derive(Copy, Clone, Debug, Default, Args)]
+#[derive(Clone, Debug, Default, Args)]
 pub struct RenderOpts {
     #[arg(short, long, value_enum, default_value_t)]
     /// Choose a specific family of units
     pub unit_family: UnitFamily,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    /// Aggregate traffic by process, process name or tree, cgroup, systemd unit, container, namespace or user
+    pub group_by: GroupBy,
+
+    #[arg(short, long, value_enum, default_value_t)]
+    /// Sort rows by this column
+    pub sort: SortBy,
+
+    #[arg(long)]
+    /// Reverse the sort order
+    pub reverse: bool,
+
+    #[arg(long, value_parser = parse_interval, default_value = "1s")]
+    /// How often to refresh, eg. 250ms or 10s
+    pub interval: Duration,
+
+    #[arg(long, value_parser = parse_smoothing)]
+    /// Smooth the current rates with a moving average over roughly this long, eg. 5s
+    pub smoothing: Option<Duration>,
+
+    #[arg(long, default_value_t = 100)]
+    /// How many refreshes to keep in the history charts
+    pub history: usize,
+
+    #[arg(long, default_value_t = 1000)]
+    /// The most rows to keep in each table
+    pub max_rows: usize,
+
+    #[arg(long, default_value_t = 10)]
+    /// Forget processes that exited after this many refreshes without traffic
+    pub evict_after: usize,
+
+    #[arg(long, default_value_t = 1000)]
+    /// The most processes to remember, forgetting the longest idle ones first
+    pub max_processes: usize,
+
+    #[arg(long)]
+    /// Keep exited processes in a dimmed section below the others until their traffic leaves the charts
+    pub show_exited: bool,
+
+    #[arg(long)]
+    /// Keep one history line for a restarting process, matched by its executable or name
+    pub merge_restarts: bool,
+
+    #[arg(long, value_enum, value_delimiter = ',')]
+    /// Comma-separated columns to show, as space allows [default: name,down,up,total-down,total-up,down-chart,up-chart]
+    pub columns: Option<Vec<Column>>,
+
+    #[arg(long)]
+    /// Show a chart of the total download and upload rates above the table
+    pub total_chart: bool,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// How to draw the rate history charts
+    pub chart_style: ChartStyle,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// What to scale the rate history charts against
+    pub chart_scale: ChartScale,
+
+    #[arg(long)]
+    /// Draw the rate history charts on a logarithmic scale
+    pub log_scale: bool,
+
+    #[arg(long, value_enum)]
+    /// Choose a color theme [default: monochrome if NO_COLOR is set, otherwise default]
+    pub theme: Option<ThemeName>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Print the traffic recorded with --record
+    Report(ReportOpts),
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ReportOpts {
+    #[arg(long, value_parser = parse_age, default_value = "1d")]
+    /// How far back to report, e.g. 12h, 7d or 4w
+    pub since: Duration,
+
+    #[arg(long, value_enum, default_value_t)]
+    /// Break the traffic down by hour, day or month
+    pub by: Rollup,
+
+    #[arg(long, value_enum)]
+    /// Only report processes or systemd units
+    pub kind: Option<UsageKind>,
+}
+
+pub fn parse_age(age: &str) -> Result<Duration, String> {
+    let split = age
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("invalid duration: {age}"))?;
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 7 * 86400,
+        _ => {
+            return Err(format!(
+                "invalid duration: {age}, expected a unit of s, m, h, d or w"
+            ))
+        }
+    };
+    Ok(Duration::from_secs(number.saturating_mul(unit)))
+}
+
+/// Parses a duration such as `250ms`, `1.5s` or `10` into seconds.
+fn parse_seconds(duration: &str) -> Option<f64> {
+    let (number, unit) = match duration.strip_suffix("ms") {
+        Some(number) => (number, 0.001),
+        None => (duration.strip_suffix('s').unwrap_or(duration), 1.0),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|number| number * unit)
+}
+
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let seconds = parse_seconds(interval).ok_or_else(|| format!("invalid interval: {interval}"))?;
+    if !(0.05..=3600.0).contains(&seconds) {
+        return Err("the interval must be between 50ms and 1h".to_string());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+fn parse_smoothing(smoothing: &str) -> Result<Duration, String> {
+    let seconds =
+        parse_seconds(smoothing).ok_or_else(|| format!("invalid smoothing time: {smoothing}"))?;
+    if !(0.0..=3600.0).contains(&seconds) {
+        return Err("the smoothing time must be between 0s and 1h".to_string());
+    }
+    Ok(Duration::from_secs_f64(seconds))
 }
 
 // IMPRV: it would be nice if we can `#[cfg_attr(not(build), derive(strum::EnumIter))]` this
@@ -48,3 +217,125 @@ pub enum UnitFamily {
     /// bits, in powers of 10^3
     SiBits,
 }
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum GroupBy {
+    #[default]
+    /// one row per process
+    Process,
+    /// one row per process name
+    Name,
+    /// processes nested under their closest ancestor with network activity
+    Tree,
+    /// cgroup v2 path (Linux only)
+    Cgroup,
+    /// systemd unit or slice (Linux only)
+    Unit,
+    /// docker/podman/containerd container (Linux only)
+    Container,
+    /// network namespace, with --all-netns (Linux only)
+    Netns,
+    /// owning user
+    User,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum SortBy {
+    /// current download rate
+    Down,
+    /// current upload rate
+    Up,
+    #[default]
+    /// total bytes downloaded
+    TotalDown,
+    /// total bytes uploaded
+    TotalUp,
+    /// current download and upload rates combined
+    Combined,
+    /// name, alphabetically
+    Name,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum ChartStyle {
+    #[default]
+    /// bars, 8 steps per line
+    Bars,
+    /// braille line, 2 points per column and 4 steps per line
+    Braille,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum ChartScale {
+    #[default]
+    /// the busiest row of the table
+    Global,
+    /// each row's own peak
+    Row,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum Rollup {
+    Hour,
+    #[default]
+    Day,
+    Month,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, ValueEnum, EnumIter)]
+pub enum UsageKind {
+    /// process name
+    Process,
+    /// systemd unit or slice (Linux only)
+    Unit,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Solarized,
+    HighContrast,
+    /// no colors at all
+    Monochrome,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum, EnumIter)]
+pub enum Column {
+    /// name of the process or group
+    Name,
+    /// process ID
+    Pid,
+    /// owner of the process
+    User,
+    /// number of connections
+    Connections,
+    /// current download rate
+    Down,
+    /// current upload rate
+    Up,
+    /// current download and upload rates combined
+    Combined,
+    /// total bytes downloaded
+    TotalDown,
+    /// total bytes uploaded
+    TotalUp,
+    /// download rate history
+    DownChart,
+    /// upload rate history
+    UpChart,
+    /// highest download rate within the history window
+    PeakDown,
+    /// highest upload rate within the history window
+    PeakUp,
+    /// mean download rate over the history window
+    MeanDown,
+    /// mean upload rate over the history window
+    MeanUp,
+    /// 95th percentile of the download rate within the history window
+    P95Down,
+    /// 95th percentile of the upload rate within the history window
+    P95Up,
+    /// how long there was any traffic
+    Active,
+}
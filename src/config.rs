@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use clap::ValueEnum;
+use eyre::{eyre, WrapErr};
+use serde::Deserialize;
+
+use crate::{
+    alerts::AlertRule,
+    cli::ThemeName,
+    keymap::{Action, KeyBinding, KeymapPreset},
+};
+
+/// Settings read from `$XDG_CONFIG_HOME/nethugs/config.toml` (or the platform's
+/// equivalent). Every setting is optional.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The theme to use unless `--theme` is given.
+    pub theme: Option<String>,
+    /// Colors overriding those of the theme.
+    pub colors: ColorConfig,
+    /// Key bindings.
+    pub keys: KeysConfig,
+    /// Rules raising alerts when traffic goes above a limit.
+    pub alerts: Vec<AlertRule>,
+}
+
+/// Colors, as names (`red`, `lightblue`), `#rrggbb` or 256-color indices.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    pub title: Option<String>,
+    pub paused: Option<String>,
+    pub table_header: Option<String>,
+    pub footer: Option<String>,
+    pub status: Option<String>,
+    pub selection: Option<String>,
+    pub alert: Option<String>,
+    pub download_start: Option<String>,
+    pub download_end: Option<String>,
+    pub upload_start: Option<String>,
+    pub upload_end: Option<String>,
+}
+
+/// Key bindings: a preset, and lists of keys replacing those of individual actions.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub preset: Option<KeymapPreset>,
+    pub bind: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("nethugs").join("config.toml"))
+    }
+
+    /// Reads the config file, if there is one.
+    pub fn load() -> eyre::Result<Self> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("Cannot read {}", path.display()))
+            }
+        };
+        toml::from_str(&content).wrap_err_with(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn theme_name(&self) -> eyre::Result<Option<ThemeName>> {
+        self.theme
+            .as_deref()
+            .map(|theme| {
+                ThemeName::from_str(theme, true).map_err(|err| eyre!("Invalid theme: {err}"))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config: Config = toml::from_str(
+            r##"
+                theme = "high-contrast"
+
+                [colors]
+                download_start = "#00ff00"
+                selection = "blue"
+
+                [keys]
+                preset = "htop"
+                bind = { quit = ["ctrl-c", "F10"] }
+
+                [[alerts]]
+                name = "rsync upload"
+                process = "rsync"
+                direction = "upload"
+                rate = "5MiB/s"
+                for = "30s"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.theme_name().unwrap(), Some(ThemeName::HighContrast));
+        assert_eq!(config.colors.download_start.as_deref(), Some("#00ff00"));
+        assert_eq!(config.keys.preset, Some(KeymapPreset::Htop));
+        assert_eq!(config.keys.bind[&Action::Quit].len(), 2);
+        assert_eq!(config.alerts[0].process.as_deref(), Some("rsync"));
+
+        assert!(toml::from_str::<Config>("colour = 'red'").is_err());
+        assert!(toml::from_str::<Config>("keys.bind.quit = ['hyper-q']").is_err());
+        assert_eq!(Config::default().theme_name().unwrap(), None);
+    }
+}
@@ -0,0 +1,239 @@
+use ratatui::layout::{Alignment, Rect};
+
+use crate::cli::{Column, SortBy};
+
+/// Columns shown unless `--columns` is given.
+pub const DEFAULT_COLUMNS: [Column; 7] = [
+    Column::Name,
+    Column::Down,
+    Column::Up,
+    Column::TotalDown,
+    Column::TotalUp,
+    Column::DownChart,
+    Column::UpChart,
+];
+
+/// How a column is laid out.
+#[derive(Clone, Debug)]
+pub struct ColumnSpec {
+    pub title: String,
+    pub min_width: u16,
+    /// `None` if the column takes whatever space is left.
+    pub max_width: Option<u16>,
+    /// Columns with a lower value are kept when space runs out.
+    pub priority: u8,
+    pub align: Alignment,
+    /// The sort order this column shows.
+    pub sort: Option<SortBy>,
+}
+
+impl ColumnSpec {
+    pub fn new(
+        title: impl Into<String>,
+        min_width: u16,
+        max_width: Option<u16>,
+        priority: u8,
+    ) -> Self {
+        ColumnSpec {
+            title: title.into(),
+            min_width,
+            max_width,
+            priority,
+            align: Alignment::Left,
+            sort: None,
+        }
+    }
+
+    pub fn align(self, align: Alignment) -> Self {
+        ColumnSpec { align, ..self }
+    }
+
+    pub fn sort(self, sort: SortBy) -> Self {
+        ColumnSpec {
+            sort: Some(sort),
+            ..self
+        }
+    }
+}
+
+impl Column {
+    /// Whether the column shows traffic, as opposed to describing the row.
+    pub fn is_traffic(self) -> bool {
+        !matches!(
+            self,
+            Column::Name | Column::Pid | Column::User | Column::Connections
+        )
+    }
+
+    pub fn spec(self) -> ColumnSpec {
+        let rate = |title| ColumnSpec::new(title, 12, Some(12), 0).align(Alignment::Right);
+        match self {
+            Column::Name => ColumnSpec::new("Name", 16, Some(48), 0).sort(SortBy::Name),
+            Column::Pid => ColumnSpec::new("PID", 7, Some(7), 9).align(Alignment::Right),
+            Column::User => ColumnSpec::new("User", 8, Some(16), 10),
+            Column::Connections => ColumnSpec::new("Conns", 5, Some(5), 8).align(Alignment::Right),
+            Column::Down => ColumnSpec {
+                priority: 1,
+                ..rate("Down")
+            }
+            .sort(SortBy::Down),
+            Column::Up => ColumnSpec {
+                priority: 2,
+                ..rate("Up")
+            }
+            .sort(SortBy::Up),
+            Column::Combined => ColumnSpec {
+                priority: 3,
+                ..rate("Down+Up")
+            }
+            .sort(SortBy::Combined),
+            Column::TotalDown => ColumnSpec {
+                priority: 6,
+                ..rate("Total Down")
+            }
+            .sort(SortBy::TotalDown),
+            Column::TotalUp => ColumnSpec {
+                priority: 7,
+                ..rate("Total Up")
+            }
+            .sort(SortBy::TotalUp),
+            Column::DownChart => ColumnSpec::new("Down", 10, None, 4),
+            Column::UpChart => ColumnSpec::new("Up", 10, None, 5),
+            Column::PeakDown => ColumnSpec {
+                priority: 11,
+                ..rate("Peak Down")
+            },
+            Column::PeakUp => ColumnSpec {
+                priority: 12,
+                ..rate("Peak Up")
+            },
+            Column::MeanDown => ColumnSpec {
+                priority: 13,
+                ..rate("Mean Down")
+            },
+            Column::MeanUp => ColumnSpec {
+                priority: 14,
+                ..rate("Mean Up")
+            },
+            Column::P95Down => ColumnSpec {
+                priority: 15,
+                ..rate("P95 Down")
+            },
+            Column::P95Up => ColumnSpec {
+                priority: 16,
+                ..rate("P95 Up")
+            },
+            Column::Active => ColumnSpec::new("Active", 8, Some(8), 17).align(Alignment::Right),
+        }
+    }
+}
+
+/// Picks the columns that fit in `width`, most important first, and widens them to
+/// fill the remaining space. Returns the width of each column, or `None` if it is
+/// hidden.
+pub fn fit_columns(specs: &[ColumnSpec], width: u16, gap: u16) -> Vec<Option<u16>> {
+    let mut by_priority = (0..specs.len()).collect::<Vec<_>>();
+    by_priority.sort_by_key(|&index| specs[index].priority);
+
+    let mut widths = vec![None; specs.len()];
+    let mut used = 0_u16;
+    for index in by_priority {
+        let needed = specs[index].min_width + if used == 0 { 0 } else { gap };
+        if used + needed <= width {
+            used += needed;
+            widths[index] = Some(specs[index].min_width);
+        }
+    }
+
+    // hand out the rest evenly, bounded columns first so that the unbounded ones
+    // (charts) get whatever is left over
+    let mut left = width - used;
+    for bounded in [true, false] {
+        loop {
+            let growable = (0..specs.len())
+                .filter(|&index| {
+                    widths[index].is_some_and(|width| {
+                        specs[index].max_width.is_some() == bounded
+                            && specs[index].max_width.is_none_or(|max| width < max)
+                    })
+                })
+                .collect::<Vec<_>>();
+            if left == 0 || growable.is_empty() {
+                break;
+            }
+            let share = (left / growable.len() as u16).max(1);
+            for index in growable {
+                let Some(ref mut width) = widths[index] else {
+                    continue;
+                };
+                let room = specs[index].max_width.map_or(u16::MAX, |max| max - *width);
+                let extra = share.min(room).min(left);
+                *width += extra;
+                left -= extra;
+            }
+        }
+    }
+    widths
+}
+
+/// Places the visible columns side by side in `rect`.
+pub fn column_rects(rect: Rect, widths: &[Option<u16>], gap: u16) -> Vec<Option<Rect>> {
+    let mut x = rect.x;
+    widths
+        .iter()
+        .map(|width| {
+            width.map(|width| {
+                let col = Rect {
+                    x,
+                    y: rect.y,
+                    width: width.min(rect.right().saturating_sub(x)),
+                    height: rect.height,
+                };
+                x = x.saturating_add(width + gap);
+                col
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_terminals_hide_columns() {
+        let specs = DEFAULT_COLUMNS.map(Column::spec);
+        // no room for the total upload
+        let widths = fit_columns(&specs, 80, 1);
+        assert_eq!(
+            widths,
+            [
+                Some(19),
+                Some(12),
+                Some(12),
+                Some(12),
+                None,
+                Some(10),
+                Some(10)
+            ]
+        );
+        assert_eq!(widths.iter().flatten().sum::<u16>() + 5, 80);
+
+        let widths = fit_columns(&specs, 20, 1);
+        assert_eq!(widths, [Some(20), None, None, None, None, None, None]);
+    }
+
+    #[test]
+    fn wide_terminals_widen_columns() {
+        let specs = DEFAULT_COLUMNS.map(Column::spec);
+        let widths = fit_columns(&specs, 200, 1);
+        assert!(widths.iter().all(Option::is_some));
+        assert_eq!(widths[0], Some(48));
+        assert_eq!(widths[1], Some(12));
+        assert_eq!(widths.iter().flatten().sum::<u16>() + 6, 200);
+
+        let rects = column_rects(Rect::new(0, 0, 200, 1), &widths, 1);
+        assert_eq!(rects[1].map(|rect| rect.x), Some(49));
+        assert_eq!(rects[6].map(|rect| rect.right()), Some(200));
+    }
+}
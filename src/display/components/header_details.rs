@@ -2,7 +2,7 @@ use std::time::{Duration, Instant};
 
 use ratatui::{
     layout::{Alignment, Rect},
-    style::{Color, Modifier, Style},
+    style::Style,
     text::Span,
     widgets::Paragraph,
     Frame,
@@ -43,10 +43,10 @@ pub struct HeaderDetails<'a> {
 impl HeaderDetails<'_> {
     pub fn render(&self, frame: &mut Frame, rect: Rect) {
         let bandwidth = self.bandwidth_string();
-        let color = if self.paused {
-            Color::Yellow
+        let style = if self.paused {
+            self.state.theme.paused
         } else {
-            Color::Green
+            self.state.theme.title
         };
 
         // do not render time in tests, otherwise the output becomes non-deterministic
@@ -54,18 +54,15 @@ impl HeaderDetails<'_> {
         if cfg!(not(test)) {
             let elapsed_time = format_duration(self.elapsed_time);
             if bandwidth.width() + 1 + elapsed_time.width() <= rect.width as usize {
-                self.render_elapsed_time(frame, rect, &elapsed_time, color);
+                self.render_elapsed_time(frame, rect, &elapsed_time, style);
             }
         }
 
-        self.render_bandwidth(frame, rect, &bandwidth, color);
+        self.render_bandwidth(frame, rect, &bandwidth, style);
     }
 
-    fn render_bandwidth(&self, frame: &mut Frame, rect: Rect, bandwidth: &str, color: Color) {
-        let bandwidth_text = Span::styled(
-            bandwidth,
-            Style::default().fg(color).add_modifier(Modifier::BOLD),
-        );
+    fn render_bandwidth(&self, frame: &mut Frame, rect: Rect, bandwidth: &str, style: Style) {
+        let bandwidth_text = Span::styled(bandwidth, style);
 
         let paragraph = Paragraph::new(bandwidth_text).alignment(Alignment::Left);
         frame.render_widget(paragraph, rect);
@@ -83,15 +80,27 @@ impl HeaderDetails<'_> {
             bandwidth: self.state.total_bytes_downloaded as f64,
             unit_family,
         };
-        let paused = if self.paused { " [PAUSED]" } else { "" };
-        format!("IF: {intrf} | Total {t} (Up / Down): {up} / {down}{paused}")
+        let paused = match (self.paused, self.state.history_cursor_time()) {
+            (true, Some(at)) => format!(" [PAUSED, AT {}]", at.format("%H:%M:%S")),
+            (true, None) => " [PAUSED]".to_string(),
+            (false, _) => String::new(),
+        };
+        let user = match self.state.only_uid {
+            Some(uid) => match self.state.user_names.get(&uid) {
+                Some(name) => format!(" | User: {name}"),
+                None => format!(" | UID: {uid}"),
+            },
+            None => String::new(),
+        };
+        let netns = match self.state.netns {
+            Some(ref netns) => format!(" | NS: {netns}"),
+            None => String::new(),
+        };
+        format!("IF: {intrf}{netns}{user} | Total {t} (Up / Down): {up} / {down}{paused}")
     }
 
-    fn render_elapsed_time(&self, frame: &mut Frame, rect: Rect, elapsed_time: &str, color: Color) {
-        let elapsed_time_text = Span::styled(
-            elapsed_time,
-            Style::default().fg(color).add_modifier(Modifier::BOLD),
-        );
+    fn render_elapsed_time(&self, frame: &mut Frame, rect: Rect, elapsed_time: &str, style: Style) {
+        let elapsed_time_text = Span::styled(elapsed_time, style);
         let paragraph = Paragraph::new(elapsed_time_text).alignment(Alignment::Right);
         frame.render_widget(paragraph, rect);
     }
@@ -1,5 +1,7 @@
 mod display_bandwidth;
 mod header_details;
+mod total_chart;
 
 pub use display_bandwidth::*;
 pub use header_details::*;
+pub use total_chart::*;
@@ -0,0 +1,158 @@
+use std::collections::VecDeque;
+
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{Axis, Block, Chart, Dataset, GraphType},
+    Frame,
+};
+
+use crate::display::{DisplayBandwidth, UIState};
+
+/// Leaves some room above the highest sample.
+const HEADROOM: f64 = 1.1;
+
+/// Download and upload rates of all traffic over the retained history.
+pub struct TotalChart<'a> {
+    pub state: &'a UIState,
+}
+
+impl TotalChart<'_> {
+    pub fn render(&self, frame: &mut Frame, rect: Rect) {
+        let history = &self.state.total_history;
+        let theme = &self.state.theme;
+        let bandwidth = |bandwidth: f64| DisplayBandwidth {
+            bandwidth,
+            unit_family: self.state.unit_family,
+        };
+
+        // on a log scale, samples and labels are mapped to and from ln(1 + rate)
+        let scale = |value: f64| match self.state.log_scale {
+            true => value.ln_1p(),
+            false => value,
+        };
+        let unscale = |value: f64| match self.state.log_scale {
+            true => value.exp_m1(),
+            false => value,
+        };
+        let points = |samples: &VecDeque<f64>| {
+            // the newest sample sits at x = 0, older ones to its left
+            let len = samples.len() as f64;
+            samples
+                .iter()
+                .enumerate()
+                .map(|(index, &value)| (index as f64 + 1.0 - len, scale(value)))
+                .collect::<Vec<_>>()
+        };
+        let download = points(&history.download_history);
+        let upload = points(&history.upload_history);
+        let max = history
+            .download_history
+            .iter()
+            .chain(&history.upload_history)
+            .copied()
+            .fold(0.0_f64, f64::max);
+        let max = scale(max);
+        let top = match max > 0.0 {
+            true => max * HEADROOM,
+            false => 1.0,
+        };
+        let span = history.download_history.len().max(2) as f64 - 1.0;
+
+        // while looking back, mark the moment and show its rates
+        let cursor = self
+            .state
+            .history_cursor
+            .map(|back| [(-(back as f64), 0.0), (-(back as f64), top)]);
+        let rate_at = |samples: &VecDeque<f64>, current: u128| match self.state.history_cursor {
+            Some(back) => samples.iter().rev().nth(back).copied().unwrap_or_default(),
+            None => current as f64,
+        };
+        let download_rate = rate_at(&history.download_history, history.current_bytes_downloaded);
+        let upload_rate = rate_at(&history.upload_history, history.current_bytes_uploaded);
+
+        let title = Line::from(vec![
+            Span::raw(" Total: "),
+            Span::styled(
+                format!("↓ {}/s", bandwidth(download_rate)),
+                Style::default().fg(theme.download.0),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                format!("↑ {}/s ", bandwidth(upload_rate)),
+                Style::default().fg(theme.upload.0),
+            ),
+        ]);
+        let mut datasets = vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.download.0))
+                .data(&download),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.upload.0))
+                .data(&upload),
+        ];
+        if let Some(ref cursor) = cursor {
+            datasets.push(
+                Dataset::default()
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(theme.muted)
+                    .data(cursor),
+            );
+        }
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .style(theme.muted)
+                    .bounds([-span, 0.0])
+                    .labels([
+                        format!("-{:?}", self.state.interval.mul_f64(span)),
+                        "now".to_string(),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(theme.muted)
+                    .bounds([0.0, top])
+                    .labels([
+                        "0".to_string(),
+                        format!("{}/s", bandwidth(unscale(top / 2.0))),
+                        format!("{}/s", bandwidth(unscale(top))),
+                    ]),
+            );
+        frame.render_widget(chart, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use insta::assert_snapshot;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::display::{components::TotalChart, UIState};
+
+    #[test]
+    fn total_chart() {
+        let mut state = UIState::default();
+        state.interval = Duration::from_secs(1);
+        state.total_history.download_history = VecDeque::from([0.0, 2000.0, 8000.0, 4000.0]);
+        state.total_history.upload_history = VecDeque::from([1000.0, 1000.0, 0.0, 500.0]);
+        state.total_history.current_bytes_downloaded = 4000;
+        state.total_history.current_bytes_uploaded = 500;
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal
+            .draw(|frame| TotalChart { state: &state }.render(frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(format!("{:?}", terminal.backend().buffer()));
+    }
+}
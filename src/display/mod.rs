@@ -1,9 +1,15 @@
+mod columns;
 mod components;
 mod raw_terminal_backend;
+mod theme;
 mod ui;
 mod ui_state;
+mod unknown;
 
+pub use columns::*;
 pub use components::*;
 pub use raw_terminal_backend::*;
+pub use theme::*;
 pub use ui::*;
 pub use ui_state::*;
+pub use unknown::*;
@@ -0,0 +1,195 @@
+use std::{env, str::FromStr};
+
+use eyre::eyre;
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::{
+    cli::ThemeName,
+    config::{ColorConfig, Config},
+};
+
+/// The colors of the UI.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// The header line while live.
+    pub title: Style,
+    /// The header line while paused.
+    pub paused: Style,
+    pub table_header: Style,
+    pub footer: Style,
+    pub status: Style,
+    /// The row under the cursor.
+    pub selection: Style,
+    /// Names of rows an alert fired for.
+    pub alert: Style,
+    /// Placeholders such as "No traffic yet".
+    pub muted: Style,
+    /// Colors of the download charts, from the lowest to the highest bars.
+    pub download: (Color, Color),
+    pub upload: (Color, Color),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new(ThemeName::Default)
+    }
+}
+
+impl Theme {
+    pub fn new(name: ThemeName) -> Self {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        match name {
+            ThemeName::Default => Theme {
+                title: bold.fg(Color::Green),
+                paused: bold.fg(Color::Yellow),
+                table_header: bold.fg(Color::Yellow),
+                footer: bold.fg(Color::Gray),
+                status: Style::default().fg(Color::Yellow),
+                selection: Style::default().bg(Color::DarkGray),
+                alert: bold.fg(Color::Red),
+                muted: Style::default().fg(Color::Gray),
+                download: (Color::Rgb(0, 195, 255), Color::Rgb(40, 80, 255)),
+                upload: (Color::Rgb(255, 80, 200), Color::Rgb(170, 70, 255)),
+            },
+            ThemeName::Solarized => {
+                let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+                let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+                let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+                let green = Color::Rgb(0x85, 0x99, 0x00);
+                Theme {
+                    title: bold.fg(green),
+                    paused: bold.fg(yellow),
+                    table_header: bold.fg(yellow),
+                    footer: bold.fg(base1),
+                    status: Style::default().fg(Color::Rgb(0xcb, 0x4b, 0x16)),
+                    selection: Style::default().bg(Color::Rgb(0x07, 0x36, 0x42)),
+                    alert: bold.fg(Color::Rgb(0xdc, 0x32, 0x2f)),
+                    muted: Style::default().fg(base01),
+                    download: (Color::Rgb(0x2a, 0xa1, 0x98), Color::Rgb(0x26, 0x8b, 0xd2)),
+                    upload: (Color::Rgb(0xd3, 0x36, 0x82), Color::Rgb(0x6c, 0x71, 0xc4)),
+                }
+            }
+            ThemeName::HighContrast => Theme {
+                title: bold.fg(Color::LightGreen),
+                paused: bold.fg(Color::LightYellow),
+                table_header: bold.fg(Color::White).add_modifier(Modifier::UNDERLINED),
+                footer: bold.fg(Color::White),
+                status: bold.fg(Color::LightYellow),
+                selection: Style::default().fg(Color::Black).bg(Color::White),
+                alert: bold.fg(Color::LightRed),
+                muted: Style::default().fg(Color::White),
+                download: (Color::Rgb(0, 255, 255), Color::Rgb(0, 255, 0)),
+                upload: (Color::Rgb(255, 0, 255), Color::Rgb(255, 255, 0)),
+            },
+            ThemeName::Monochrome => Theme {
+                title: bold,
+                paused: bold,
+                table_header: bold,
+                footer: bold,
+                status: bold,
+                selection: Style::default().add_modifier(Modifier::REVERSED),
+                alert: bold.add_modifier(Modifier::UNDERLINED),
+                muted: Style::default(),
+                download: (Color::Reset, Color::Reset),
+                upload: (Color::Reset, Color::Reset),
+            },
+        }
+    }
+
+    /// Picks the theme given on the command line, in the config file, or implied by
+    /// `NO_COLOR`, in that order, and applies the colors of the config file to it.
+    pub fn load(theme: Option<ThemeName>, config: &Config) -> eyre::Result<Self> {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let name = match (theme, config.theme_name()?) {
+            (Some(name), _) | (None, Some(name)) => name,
+            (None, None) if no_color => ThemeName::Monochrome,
+            (None, None) => ThemeName::Default,
+        };
+        let mut theme = Theme::new(name);
+        theme.apply(&config.colors)?;
+        Ok(theme)
+    }
+
+    /// Overrides the colors of the theme with those set in the config file.
+    pub fn apply(&mut self, colors: &ColorConfig) -> eyre::Result<()> {
+        let parse = |color: &Option<String>| -> eyre::Result<Option<Color>> {
+            color
+                .as_deref()
+                .map(|color| Color::from_str(color).map_err(|_| eyre!("Invalid color: {color}")))
+                .transpose()
+        };
+        if let Some(color) = parse(&colors.title)? {
+            self.title = self.title.fg(color);
+        }
+        if let Some(color) = parse(&colors.paused)? {
+            self.paused = self.paused.fg(color);
+        }
+        if let Some(color) = parse(&colors.table_header)? {
+            self.table_header = self.table_header.fg(color);
+        }
+        if let Some(color) = parse(&colors.footer)? {
+            self.footer = self.footer.fg(color);
+        }
+        if let Some(color) = parse(&colors.status)? {
+            self.status = self.status.fg(color);
+        }
+        if let Some(color) = parse(&colors.selection)? {
+            self.selection = self.selection.bg(color);
+        }
+        if let Some(color) = parse(&colors.alert)? {
+            self.alert = self.alert.fg(color);
+        }
+        for (color, slot) in [
+            (&colors.download_start, &mut self.download.0),
+            (&colors.download_end, &mut self.download.1),
+            (&colors.upload_start, &mut self.upload.0),
+            (&colors.upload_end, &mut self.upload.1),
+        ] {
+            if let Some(color) = parse(color)? {
+                *slot = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the color at `ratio` between both ends of a gradient. Gradients can only
+/// be interpolated between RGB colors, otherwise the start color is used throughout.
+pub fn gradient_color((start, end): (Color, Color), ratio: f64) -> Color {
+    let (Color::Rgb(sr, sg, sb), Color::Rgb(er, eg, eb)) = (start, end) else {
+        return start;
+    };
+    let ratio = ratio.clamp(0.0, 1.0);
+    let mix = |s: u8, e: u8| (s as f64 + (e as f64 - s as f64) * ratio).round() as u8;
+    Color::Rgb(mix(sr, er), mix(sg, eg), mix(sb, eb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradients_and_overrides() {
+        let gradient = (Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 50));
+        assert_eq!(gradient_color(gradient, 0.5), Color::Rgb(100, 50, 25));
+        assert_eq!(gradient_color(gradient, 2.0), Color::Rgb(200, 100, 50));
+        assert_eq!(
+            gradient_color((Color::Red, Color::Rgb(1, 2, 3)), 1.0),
+            Color::Red
+        );
+
+        let mut theme = Theme::new(ThemeName::Monochrome);
+        let colors = ColorConfig {
+            upload_end: Some("#ff0000".to_string()),
+            ..Default::default()
+        };
+        theme.apply(&colors).unwrap();
+        assert_eq!(theme.upload, (Color::Reset, Color::Rgb(255, 0, 0)));
+
+        let colors = ColorConfig {
+            footer: Some("not-a-color".to_string()),
+            ..Default::default()
+        };
+        assert!(theme.apply(&colors).is_err());
+    }
+}
@@ -1,24 +1,41 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
     time::Duration,
 };
 
 use chrono::prelude::*;
+use crossterm::event::KeyEvent;
+use itertools::Itertools;
 use ratatui::{
     backend::Backend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Bar, BarChart, BarGroup, Paragraph},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{Axis, Bar, BarChart, BarGroup, Block, Chart, Clear, Dataset, GraphType, Paragraph},
     Frame, Terminal,
 };
 use unicode_width::UnicodeWidthChar;
 
+use clap::ValueEnum;
+
 use crate::{
-    cli::Opt,
-    display::{components::HeaderDetails, DisplayBandwidth, UIState},
-    network::{LocalSocket, Utilization},
-    os::ProcessInfo,
+    alerts::{Alert, AlertRule},
+    cli::{ChartScale, ChartStyle, Column, GroupBy, Opt, SortBy},
+    display::{
+        column_rects,
+        components::{HeaderDetails, TotalChart},
+        fit_columns, gradient_color, ColumnSpec, ConnectionRow, DisplayBandwidth, NetworkData,
+        ProcessRow, RateStats, RemoteRow, SignalPrompt, Theme, UIState, DEFAULT_COLUMNS,
+    },
+    keymap::{Action, Keymap},
+    network::{display_connection_string, display_ip_or_host, Resolver, Utilization},
+    os::{get_invoking_uid, get_user_names, ProcessInfo, Signal},
+    usage::UsageKey,
+    OpenSockets,
 };
 
 pub struct Ui<B>
@@ -33,7 +50,7 @@ impl<B> Ui<B>
 where
     B: Backend,
 {
-    pub fn new(terminal_backend: B, opts: &Opt) -> Self {
+    pub fn new(terminal_backend: B, opts: &Opt, theme: Theme, keymap: Keymap) -> Self {
         let mut terminal = Terminal::new(terminal_backend).unwrap();
         terminal.clear().unwrap();
         terminal.hide_cursor().unwrap();
@@ -41,6 +58,37 @@ where
             let mut state = UIState::default();
             state.interface_name.clone_from(&opts.interface);
             state.unit_family = opts.render_opts.unit_family.into();
+            state.group_by = opts.render_opts.group_by;
+            state.theme = theme;
+            state.keymap = keymap;
+            state.columns = opts
+                .render_opts
+                .columns
+                .clone()
+                .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec());
+            state.sort_by = opts.render_opts.sort;
+            state.reverse = opts.render_opts.reverse;
+            state.show_total_chart = opts.render_opts.total_chart;
+            state.chart_style = opts.render_opts.chart_style;
+            state.chart_scale = opts.render_opts.chart_scale;
+            state.log_scale = opts.render_opts.log_scale;
+            state.interval = opts.render_opts.interval;
+            state.smoothing = opts.render_opts.smoothing;
+            state.history_length = opts.render_opts.history.max(1);
+            state.max_rows = opts.render_opts.max_rows;
+            state.evict_after = opts.render_opts.evict_after;
+            state.max_processes = opts.render_opts.max_processes;
+            state.show_exited = opts.render_opts.show_exited;
+            state.merge_restarts = opts.render_opts.merge_restarts;
+            state.record_usage = opts.record;
+            state.user_names = get_user_names();
+            // raw output is meant to be parsed, so it shows addresses as they are
+            state.resolver = (!opts.no_resolve && !opts.raw).then(Resolver::new);
+            state.only_uid = opts.only_mine.then(get_invoking_uid).flatten();
+            state.netns = match opts.all_netns {
+                true => Some("all".to_string()),
+                false => opts.netns.clone(),
+            };
             state
         };
         Ui { terminal, state }
@@ -50,17 +98,36 @@ where
         let local_time: DateTime<Local> = Local::now();
         let timestamp = local_time.timestamp();
         let mut no_traffic = true;
+        let kind = state
+            .group_by
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
 
         let output_process_data = |write_to_stdout: &mut (dyn FnMut(&str) + Send),
                                    no_traffic: &mut bool| {
             for row in &state.process_rows {
+                let (kind, cause) = match row.cause {
+                    Some(ref cause) => ("unknown", format!(" ({cause})")),
+                    None => (kind.as_str(), String::new()),
+                };
+                let (download, upload) = (row.download_stats, row.upload_stats);
                 write_to_stdout(&format!(
-                    "process: <{timestamp}> \"{}\" down/up Bps: {}/{} total down/up B: {}/{}",
+                    "{kind}: <{timestamp}> \"{}\"{cause} down/up Bps: {}/{} total down/up B: {}/{} \
+                     peak/mean/p95 down Bps: {:.0}/{:.0}/{:.0} peak/mean/p95 up Bps: {:.0}/{:.0}/{:.0} \
+                     active s: {}",
                     row.process.name,
                     row.current_bytes_downloaded,
                     row.current_bytes_uploaded,
                     row.total_bytes_downloaded,
-                    row.total_bytes_uploaded
+                    row.total_bytes_uploaded,
+                    download.peak,
+                    download.mean,
+                    download.p95,
+                    upload.peak,
+                    upload.mean,
+                    upload.p95,
+                    row.active_time.as_secs()
                 ));
                 *no_traffic = false;
             }
@@ -69,8 +136,35 @@ where
         // header
         write_to_stdout("Refreshing:");
 
+        for event in &state.fired_alerts {
+            write_to_stdout(&format!("alert: <{timestamp}> {event}"));
+        }
+
         output_process_data(write_to_stdout, &mut no_traffic);
 
+        for row in &state.connection_rows {
+            write_to_stdout(&format!(
+                "connection: <{timestamp}> {} \"{}\" down/up Bps: {}/{} total down/up B: {}/{}",
+                display_connection_string(&row.connection, &state.ip_to_host),
+                row.process.name,
+                row.current_bytes_downloaded,
+                row.current_bytes_uploaded,
+                row.total_bytes_downloaded,
+                row.total_bytes_uploaded
+            ));
+        }
+        for row in &state.remote_rows {
+            write_to_stdout(&format!(
+                "remote_address: <{timestamp}> {} processes: {} down/up Bps: {}/{} total down/up B: {}/{}",
+                display_ip_or_host(row.ip, &state.ip_to_host),
+                row.processes,
+                row.current_bytes_downloaded,
+                row.current_bytes_uploaded,
+                row.total_bytes_downloaded,
+                row.total_bytes_uploaded
+            ));
+        }
+
         // body2: In case no traffic is detected
         if no_traffic {
             write_to_stdout("<NO TRAFFIC>");
@@ -80,18 +174,27 @@ where
         write_to_stdout("");
     }
 
-    pub fn draw(&mut self, paused: bool, elapsed_time: Duration, _table_cycle_offset: usize) {
+    pub fn draw(&mut self, paused: bool, elapsed_time: Duration, table_cycle_offset: usize) {
+        let table = TABLES[table_cycle_offset % TABLES.len()];
         self.terminal
             .draw(|frame| {
                 let area = frame.area();
+                let status = self.state.status().map(str::to_string);
                 let layout = Layout::default()
                     .direction(Direction::Vertical)
                     .constraints([
                         Constraint::Length(1),
+                        Constraint::Length(match self.state.show_total_chart {
+                            true => TOTAL_CHART_HEIGHT,
+                            false => 0,
+                        }),
                         Constraint::Min(1),
+                        Constraint::Length(status.is_some() as u16),
                         Constraint::Length(1),
                     ])
                     .split(area);
+                let row_slots = layout[2].height.saturating_sub(HEADER_HEIGHT) / ROW_HEIGHT;
+                self.state.scroll_into_view(table, row_slots as usize);
 
                 let header = HeaderDetails {
                     state: &self.state,
@@ -99,23 +202,185 @@ where
                     paused,
                 };
                 header.render(frame, layout[0]);
+                if self.state.show_total_chart {
+                    TotalChart { state: &self.state }.render(frame, layout[1]);
+                }
 
-                render_process_table(frame, layout[1], &self.state);
-                render_footer(frame, layout[2], paused);
+                match (&self.state.detail, table) {
+                    (Some(process), _) => render_detail(frame, layout[2], &self.state, process),
+                    (None, Table::Processes) => render_process_table(frame, layout[2], &self.state),
+                    (None, Table::Connections) => {
+                        render_connection_table(frame, layout[2], &self.state)
+                    }
+                    (None, Table::Remotes) => render_remote_table(frame, layout[2], &self.state),
+                }
+                if let Some(status) = status {
+                    let status = Paragraph::new(status).style(self.state.theme.status);
+                    frame.render_widget(status, layout[3]);
+                }
+                render_footer(frame, layout[4], paused, &self.state);
+                if let Some(ref prompt) = self.state.signal_prompt {
+                    render_signal_prompt(frame, area, prompt, &self.state.theme);
+                }
+                if let Some(offset) = self.state.help {
+                    render_help(frame, area, offset, &self.state);
+                }
             })
             .unwrap();
     }
 
     pub fn get_table_count(&self) -> usize {
-        1
+        TABLES.len()
     }
 
-    pub fn update_state(
-        &mut self,
-        connections_to_procs: HashMap<LocalSocket, ProcessInfo>,
-        utilization: Utilization,
-    ) {
-        self.state.update(connections_to_procs, utilization);
+    pub fn set_local_ips(&mut self, local_ips: HashSet<IpAddr>) {
+        self.state.local_ips = local_ips;
+    }
+    pub fn account(&mut self, open_sockets: &OpenSockets, utilization: &Utilization) {
+        self.state.account(open_sockets, utilization);
+    }
+    pub fn update_state(&mut self, open_sockets: OpenSockets, utilization: Utilization) {
+        self.state.update(open_sockets, utilization);
+    }
+    pub fn set_alert_rules(&mut self, rules: Vec<AlertRule>) {
+        self.state.alerts = rules.into_iter().map(Alert::new).collect();
+    }
+    pub fn seed_alerts(&mut self, day: NaiveDate, recorded: &HashMap<String, NetworkData>) {
+        for alert in self.state.alerts.iter_mut() {
+            alert.seed_today(day, recorded);
+        }
+    }
+    pub fn take_usage(&mut self) -> HashMap<UsageKey, NetworkData> {
+        self.state.take_usage()
+    }
+    pub fn cycle_group_by(&mut self) {
+        self.state.cycle_group_by();
+    }
+    pub fn toggle_collapse(&mut self) {
+        self.state.toggle_collapse();
+    }
+    pub fn toggle_collapse_all(&mut self) {
+        self.state.toggle_collapse_all();
+    }
+    pub fn cycle_sort_by(&mut self) {
+        self.state.cycle_sort_by();
+    }
+    pub fn toggle_reverse(&mut self) {
+        self.state.toggle_reverse();
+    }
+    pub fn toggle_total_chart(&mut self) {
+        self.state.toggle_total_chart();
+    }
+    pub fn refresh_interval(&self) -> Duration {
+        self.state.interval
+    }
+    pub fn step_interval(&mut self, steps: isize) {
+        self.state.step_interval(steps);
+    }
+    pub fn step_history_length(&mut self, steps: isize) {
+        self.state.step_history_length(steps);
+    }
+    pub fn move_history_cursor(&mut self, delta: isize) {
+        self.state.move_history_cursor(delta);
+    }
+    pub fn clear_history_cursor(&mut self) {
+        self.state.clear_history_cursor();
+    }
+    pub fn cycle_chart_style(&mut self) {
+        self.state.cycle_chart_style();
+    }
+    pub fn cycle_chart_scale(&mut self) {
+        self.state.cycle_chart_scale();
+    }
+    pub fn toggle_log_scale(&mut self) {
+        self.state.toggle_log_scale();
+    }
+    pub fn move_selection(&mut self, table_cycle_offset: usize, delta: isize) {
+        let table = TABLES[table_cycle_offset % TABLES.len()];
+        self.state.move_selection(table, delta);
+    }
+    pub fn move_page(&mut self, table_cycle_offset: usize, pages: isize) {
+        let table = TABLES[table_cycle_offset % TABLES.len()];
+        self.state.move_page(table, pages);
+    }
+    pub fn select_first(&mut self, table_cycle_offset: usize) {
+        let table = TABLES[table_cycle_offset % TABLES.len()];
+        self.state.select_first(table);
+    }
+    pub fn select_last(&mut self, table_cycle_offset: usize) {
+        let table = TABLES[table_cycle_offset % TABLES.len()];
+        self.state.select_last(table);
+    }
+    pub fn open_detail(&mut self, table_cycle_offset: usize) {
+        if TABLES[table_cycle_offset % TABLES.len()] == Table::Processes {
+            self.state.open_detail();
+        }
+    }
+    /// Closes the detail view if it is open, or clears the filter otherwise.
+    pub fn back(&mut self) {
+        match self.state.detail {
+            Some(_) => self.state.close_detail(),
+            None => self.state.clear_filter(),
+        }
+    }
+    /// Asks for a signal to send to the process of the detail view, or of the selected
+    /// row of the process table.
+    pub fn prompt_signal(&mut self, table_cycle_offset: usize, signal: Option<Signal>) {
+        let target = match self.state.detail {
+            Some(ref process) => Some(process.clone()),
+            None if TABLES[table_cycle_offset % TABLES.len()] == Table::Processes => {
+                self.state.selected_process().map(|row| row.process.clone())
+            }
+            None => None,
+        };
+        if let Some(target) = target {
+            self.state.prompt_signal(target, signal);
+        }
+    }
+    pub fn is_prompting_signal(&self) -> bool {
+        self.state.signal_prompt.is_some()
+    }
+    pub fn move_signal_cursor(&mut self, delta: isize) {
+        self.state.move_signal_cursor(delta);
+    }
+    pub fn choose_signal(&mut self) {
+        self.state.choose_signal();
+    }
+    pub fn confirm_signal(&mut self) {
+        self.state.confirm_signal();
+    }
+    pub fn cancel_signal(&mut self) {
+        self.state.cancel_signal();
+    }
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.state.keymap.action(key)
+    }
+    pub fn is_showing_help(&self) -> bool {
+        self.state.help.is_some()
+    }
+    pub fn toggle_help(&mut self) {
+        self.state.toggle_help();
+    }
+    pub fn scroll_help(&mut self, delta: isize) {
+        self.state.scroll_help(delta);
+    }
+    pub fn is_editing_filter(&self) -> bool {
+        self.state.editing_filter
+    }
+    pub fn start_filter(&mut self) {
+        self.state.start_filter();
+    }
+    pub fn push_filter_char(&mut self, ch: char) {
+        self.state.push_filter_char(ch);
+    }
+    pub fn pop_filter_char(&mut self) {
+        self.state.pop_filter_char();
+    }
+    pub fn confirm_filter(&mut self) {
+        self.state.confirm_filter();
+    }
+    pub fn clear_filter(&mut self) {
+        self.state.clear_filter();
     }
     pub fn end(&mut self) {
         self.terminal.show_cursor().unwrap();
@@ -125,21 +390,378 @@ where
 const HEADER_HEIGHT: u16 = 1;
 const ROW_HEIGHT: u16 = 1;
 const COLUMN_GAP: u16 = 1;
-const CHART_COLOR_START: Color = Color::Rgb(0, 195, 255);
-const CHART_COLOR_END: Color = Color::Rgb(170, 70, 255);
+/// Resolution of a bar chart, per terminal row.
+const TICKS_PER_ROW: u64 = 8;
+/// Height of each chart of the detail view, borders included.
+const DETAIL_CHART_HEIGHT: u16 = 10;
+/// Height of the total bandwidth chart, borders and axis labels included.
+const TOTAL_CHART_HEIGHT: u16 = 10;
+
+/// The tables that can be cycled through with <TAB>.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Table {
+    Processes,
+    Connections,
+    Remotes,
+}
+
+const TABLES: [Table; 3] = [Table::Processes, Table::Connections, Table::Remotes];
+
+/// The traffic columns of a table row.
+pub struct Traffic<'a> {
+    /// What the row is ordered by when sorting by name.
+    name: Cow<'a, str>,
+    current_bytes_downloaded: u128,
+    current_bytes_uploaded: u128,
+    total_bytes_downloaded: u128,
+    total_bytes_uploaded: u128,
+    download_history: &'a VecDeque<f64>,
+    upload_history: &'a VecDeque<f64>,
+    active_time: Duration,
+    download_stats: RateStats,
+    upload_stats: RateStats,
+    /// Whether the row is drawn dimmed, as it belongs to an exited process.
+    dimmed: bool,
+    /// Whether the labels of the row are highlighted, as an alert fired for it.
+    alerting: bool,
+}
+
+impl<'a> From<&'a ProcessRow> for Traffic<'a> {
+    fn from(row: &'a ProcessRow) -> Self {
+        Traffic {
+            name: Cow::Borrowed(&row.process.name),
+            current_bytes_downloaded: row.current_bytes_downloaded,
+            current_bytes_uploaded: row.current_bytes_uploaded,
+            total_bytes_downloaded: row.total_bytes_downloaded,
+            total_bytes_uploaded: row.total_bytes_uploaded,
+            download_history: &row.download_history,
+            upload_history: &row.upload_history,
+            active_time: row.active_time,
+            download_stats: row.download_stats,
+            upload_stats: row.upload_stats,
+            dimmed: row.exited,
+            alerting: row.alerting,
+        }
+    }
+}
+
+impl<'a> From<&'a ConnectionRow> for Traffic<'a> {
+    fn from(row: &'a ConnectionRow) -> Self {
+        Traffic {
+            name: Cow::Borrowed(&row.process.name),
+            current_bytes_downloaded: row.current_bytes_downloaded,
+            current_bytes_uploaded: row.current_bytes_uploaded,
+            total_bytes_downloaded: row.total_bytes_downloaded,
+            total_bytes_uploaded: row.total_bytes_uploaded,
+            download_history: &row.download_history,
+            upload_history: &row.upload_history,
+            active_time: row.active_time,
+            download_stats: row.download_stats,
+            upload_stats: row.upload_stats,
+            dimmed: false,
+            alerting: false,
+        }
+    }
+}
+
+impl<'a> From<&'a RemoteRow> for Traffic<'a> {
+    fn from(row: &'a RemoteRow) -> Self {
+        Traffic {
+            name: Cow::Owned(row.ip.to_string()),
+            current_bytes_downloaded: row.current_bytes_downloaded,
+            current_bytes_uploaded: row.current_bytes_uploaded,
+            total_bytes_downloaded: row.total_bytes_downloaded,
+            total_bytes_uploaded: row.total_bytes_uploaded,
+            download_history: &row.download_history,
+            upload_history: &row.upload_history,
+            active_time: row.active_time,
+            download_stats: row.download_stats,
+            upload_stats: row.upload_stats,
+            dimmed: false,
+            alerting: false,
+        }
+    }
+}
+
+/// Orders rows by the sort column: largest first, or alphabetically for names. Ties
+/// are broken by name, so that rows do not jump around between refreshes.
+pub fn compare_rows<'a, T>(a: &'a T, b: &'a T, sort_by: SortBy, reverse: bool) -> Ordering
+where
+    Traffic<'a>: From<&'a T>,
+{
+    let (a, b) = (Traffic::from(a), Traffic::from(b));
+    let ordering = match sort_by {
+        SortBy::Down => b.current_bytes_downloaded.cmp(&a.current_bytes_downloaded),
+        SortBy::Up => b.current_bytes_uploaded.cmp(&a.current_bytes_uploaded),
+        SortBy::TotalDown => b.total_bytes_downloaded.cmp(&a.total_bytes_downloaded),
+        SortBy::TotalUp => b.total_bytes_uploaded.cmp(&a.total_bytes_uploaded),
+        SortBy::Combined => (b.current_bytes_downloaded + b.current_bytes_uploaded)
+            .cmp(&(a.current_bytes_downloaded + a.current_bytes_uploaded)),
+        SortBy::Name => Ordering::Equal,
+    };
+    let ordering = ordering.then_with(|| a.name.cmp(&b.name));
+    match reverse {
+        true => ordering.reverse(),
+        false => ordering,
+    }
+}
+
+/// A table: columns describing each row, followed by the traffic columns chosen by
+/// the user.
+struct TableLayout<'a> {
+    labels: Vec<ColumnSpec>,
+    rows: Vec<(Vec<String>, Traffic<'a>)>,
+    /// Index of the highlighted row.
+    selected: Option<usize>,
+    /// Index of the first visible row.
+    offset: usize,
+}
 
 fn render_process_table(frame: &mut Frame, rect: Rect, state: &UIState) {
+    let columns = state
+        .columns
+        .iter()
+        .copied()
+        .filter(|column| !column.is_traffic())
+        .collect::<Vec<_>>();
+    let labels = columns
+        .iter()
+        .map(|column| match column {
+            Column::Name => ColumnSpec {
+                title: group_title(state.group_by).to_string(),
+                ..column.spec()
+            },
+            _ => column.spec(),
+        })
+        .collect();
+    let rows = state
+        .process_rows
+        .iter()
+        .map(|row| {
+            let cells = columns
+                .iter()
+                .map(|&column| process_cell(column, row, state))
+                .collect();
+            (cells, Traffic::from(row))
+        })
+        .collect();
+    let table = TableLayout {
+        labels,
+        rows,
+        selected: Some(state.process_cursor.selected),
+        offset: state.process_cursor.offset,
+    };
+    render_table(frame, rect, table, state);
+}
+
+/// Returns the content of a descriptive column of the process table.
+fn process_cell(column: Column, row: &ProcessRow, state: &UIState) -> String {
+    let meta = state.process_meta(&row.process);
+    match column {
+        Column::Name if row.exited => format!(
+            "{} (exited)",
+            row_label(row, meta.and_then(|meta| meta.netns.as_deref()))
+        ),
+        Column::Name => row_label(row, meta.and_then(|meta| meta.netns.as_deref())),
+        Column::Pid if row.process.pid != 0 => row.process.pid.to_string(),
+        Column::User if row.process.pid != 0 => meta
+            .and_then(|meta| meta.uid)
+            .map(|uid| {
+                state
+                    .user_names
+                    .get(&uid)
+                    .cloned()
+                    .unwrap_or_else(|| uid.to_string())
+            })
+            .unwrap_or_default(),
+        Column::Connections if row.cause.is_none() => row.connections.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn render_connection_table(frame: &mut Frame, rect: Rect, state: &UIState) {
+    let rows = state
+        .connection_rows
+        .iter()
+        .map(|row| {
+            let labels = vec![
+                display_connection_string(&row.connection, &state.ip_to_host),
+                row.process.name.clone(),
+            ];
+            (labels, Traffic::from(row))
+        })
+        .collect();
+    let table = TableLayout {
+        labels: vec![
+            ColumnSpec::new("Connection", 30, Some(64), 0),
+            ColumnSpec::new("Process", 10, Some(20), 3).sort(SortBy::Name),
+        ],
+        rows,
+        selected: Some(state.connection_cursor.selected),
+        offset: state.connection_cursor.offset,
+    };
+    render_table(frame, rect, table, state);
+}
+
+fn render_remote_table(frame: &mut Frame, rect: Rect, state: &UIState) {
+    let rows = state
+        .remote_rows
+        .iter()
+        .map(|row| {
+            let labels = vec![
+                display_ip_or_host(row.ip, &state.ip_to_host),
+                row.processes.to_string(),
+            ];
+            (labels, Traffic::from(row))
+        })
+        .collect();
+    let table = TableLayout {
+        labels: vec![
+            ColumnSpec::new("Remote Address", 20, Some(48), 0).sort(SortBy::Name),
+            ColumnSpec::new("Processes", 9, Some(9), 8).align(Alignment::Right),
+        ],
+        rows,
+        selected: Some(state.remote_cursor.selected),
+        offset: state.remote_cursor.offset,
+    };
+    render_table(frame, rect, table, state);
+}
+
+/// Renders everything known about a single row of the process table.
+fn render_detail(frame: &mut Frame, rect: Rect, state: &UIState, process: &ProcessInfo) {
+    let Some(row) = state
+        .process_rows
+        .iter()
+        .find(|row| row.process == *process)
+    else {
+        let gone = Paragraph::new(format!("No traffic recorded for {}", process.name))
+            .style(state.theme.muted)
+            .alignment(Alignment::Center);
+        frame.render_widget(gone, rect);
+        return;
+    };
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Length(DETAIL_CHART_HEIGHT),
+            Constraint::Min(1),
+        ])
+        .split(rect);
+
+    let meta = state.process_meta(process);
+    let pid = match process.pid {
+        0 => "-".to_string(),
+        pid => pid.to_string(),
+    };
+    let user = meta
+        .and_then(|meta| meta.uid)
+        .map(|uid| {
+            state
+                .user_names
+                .get(&uid)
+                .cloned()
+                .unwrap_or_else(|| uid.to_string())
+        })
+        .unwrap_or_else(|| "-".to_string());
+    let cmdline = meta
+        .and_then(|meta| meta.cmdline.as_deref())
+        .unwrap_or(&process.name);
+    let rate = |bandwidth: f64| {
+        format!(
+            "{}/s",
+            DisplayBandwidth {
+                bandwidth,
+                unit_family: state.unit_family,
+            }
+        )
+    };
+    let stats = |RateStats { peak, mean, p95 }: RateStats| {
+        format!(
+            "peak {}, mean {}, p95 {}",
+            rate(peak),
+            rate(mean),
+            rate(p95)
+        )
+    };
+    let info = [
+        format!(
+            "{}  PID: {pid}  User: {user}  Active: {}",
+            process.name,
+            display_duration(row.active_time)
+        ),
+        format!("Command: {cmdline}"),
+        format!("Download: {}", stats(row.download_stats)),
+        format!("Upload: {}", stats(row.upload_stats)),
+    ];
+    frame.render_widget(Paragraph::new(info.join("\n")), layout[0]);
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(layout[1]);
+    for (chart, title, history, series) in [
+        (
+            charts[0],
+            "Download",
+            &row.download_history,
+            Series::Download,
+        ),
+        (charts[1], "Upload", &row.upload_history, Series::Upload),
+    ] {
+        let block = Block::bordered().title(title);
+        let inner = block.inner(chart);
+        frame.render_widget(block, chart);
+        let points = inner.width as usize * points_per_column(state.chart_style);
+        let samples_per_column = history.len().div_ceil(inner.width.max(1) as usize);
+        let history = downsample_history(history, points);
+        render_history(frame, inner, &history, 0.0, series, state);
+        let samples_per_column = samples_per_column.max(points_per_column(state.chart_style));
+        mark_history_cursor(frame, inner, state, samples_per_column);
+    }
+
+    let table = TableLayout {
+        labels: vec![ColumnSpec::new("Connection", 30, Some(64), 0)],
+        rows: state
+            .row_connections(process)
+            .into_iter()
+            .map(|row| {
+                let label = display_connection_string(&row.connection, &state.ip_to_host);
+                (vec![label], Traffic::from(row))
+            })
+            .collect(),
+        selected: None,
+        offset: 0,
+    };
+    render_table(frame, layout[2], table, state);
+}
+
+fn render_table(frame: &mut Frame, rect: Rect, table: TableLayout, state: &UIState) {
     if rect.height < HEADER_HEIGHT + 1 {
         return;
     }
 
+    let traffic_columns = state
+        .columns
+        .iter()
+        .copied()
+        .filter(|column| column.is_traffic())
+        .collect::<Vec<_>>();
+    let specs = table
+        .labels
+        .iter()
+        .cloned()
+        .chain(traffic_columns.iter().map(|column| column.spec()))
+        .collect::<Vec<_>>();
+    let widths = fit_columns(&specs, rect.width, COLUMN_GAP);
+
     let header_rect = Rect {
         x: rect.x,
         y: rect.y,
         width: rect.width,
         height: HEADER_HEIGHT,
     };
-    render_table_header(frame, header_rect);
+    render_table_header(frame, header_rect, &specs, &widths, state);
 
     let body_rect = Rect {
         x: rect.x,
@@ -153,159 +775,326 @@ fn render_process_table(frame: &mut Frame, rect: Rect, state: &UIState) {
         return;
     }
 
-    if state.process_rows.is_empty() {
+    if table.rows.is_empty() {
         let empty = Paragraph::new("No traffic yet")
-            .style(Style::default().fg(Color::Gray))
+            .style(state.theme.muted)
             .alignment(Alignment::Center);
         frame.render_widget(empty, body_rect);
         return;
     }
 
-    let (max_download, max_upload) = max_history_values(state);
+    let (max_download, max_upload) =
+        max_history_values(table.rows.iter().map(|(_, traffic)| traffic));
 
-    for (index, row) in state
-        .process_rows
+    let visible_rows = table
+        .rows
         .iter()
-        .take(row_slots as usize)
         .enumerate()
-    {
+        .skip(table.offset)
+        .take(row_slots as usize);
+    for (slot, (index, (labels, traffic))) in visible_rows.enumerate() {
         let row_rect = Rect {
             x: body_rect.x,
-            y: body_rect.y + (index as u16 * ROW_HEIGHT),
+            y: body_rect.y + (slot as u16 * ROW_HEIGHT),
             width: body_rect.width,
             height: ROW_HEIGHT,
         };
-        render_process_row(
-            frame,
-            row_rect,
-            row,
-            state.unit_family,
-            max_download,
-            max_upload,
-        );
+        if table.selected == Some(index) {
+            let highlight = Block::default().style(state.theme.selection);
+            frame.render_widget(highlight, row_rect);
+        }
+        let columns = column_rects(row_rect, &widths, COLUMN_GAP);
+        let (label_columns, traffic_rects) = columns.split_at(table.labels.len());
+        for ((label, spec), col) in labels.iter().zip(&table.labels).zip(label_columns) {
+            if let Some(col) = col {
+                let label = truncate_to_width(label, col.width);
+                let style = match traffic.alerting {
+                    true => state.theme.alert,
+                    false => Style::default(),
+                };
+                let label = Paragraph::new(label).style(style).alignment(spec.align);
+                frame.render_widget(label, *col);
+            }
+        }
+        for (&column, col) in traffic_columns.iter().zip(traffic_rects) {
+            if let Some(col) = col {
+                let max = match column {
+                    Column::UpChart => max_upload,
+                    _ => max_download,
+                };
+                render_traffic_cell(frame, *col, column, traffic, state, max);
+            }
+        }
+        if traffic.dimmed {
+            let dimmed = state.theme.muted.add_modifier(Modifier::DIM);
+            frame.buffer_mut().set_style(row_rect, dimmed);
+        }
     }
 }
 
-fn render_table_header(frame: &mut Frame, rect: Rect) {
-    let columns = split_columns(rect);
-    let headers = [
-        "Process",
-        "Down",
-        "Up",
-        "Total Down",
-        "Total Up",
-        "Down",
-        "Up",
-    ];
+fn render_table_header(
+    frame: &mut Frame,
+    rect: Rect,
+    specs: &[ColumnSpec],
+    widths: &[Option<u16>],
+    state: &UIState,
+) {
+    // names are sorted in ascending order, everything else in descending order
+    let indicator = match (state.sort_by == SortBy::Name) != state.reverse {
+        true => " ▲",
+        false => " ▼",
+    };
+    let columns = column_rects(rect, widths, COLUMN_GAP);
 
-    for (col, title) in columns.into_iter().zip(headers) {
-        let header = Paragraph::new(Span::styled(
-            title,
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        ))
-        .alignment(Alignment::Center);
+    for (spec, col) in specs.iter().zip(columns) {
+        let Some(col) = col else { continue };
+        let title = match spec.sort == Some(state.sort_by) {
+            true => format!("{}{indicator}", spec.title),
+            false => spec.title.clone(),
+        };
+        let header = Paragraph::new(Span::styled(title, state.theme.table_header))
+            .alignment(Alignment::Center);
         frame.render_widget(header, col);
     }
 }
 
-fn render_process_row(
+fn group_title(group_by: GroupBy) -> &'static str {
+    match group_by {
+        GroupBy::Process => "Process",
+        GroupBy::Name => "Name",
+        GroupBy::Tree => "Process Tree",
+        GroupBy::Cgroup => "Cgroup",
+        GroupBy::Unit => "Unit",
+        GroupBy::Container => "Container",
+        GroupBy::Netns => "Namespace",
+        GroupBy::User => "User",
+    }
+}
+
+/// Returns the name of a row, indented and marked according to its place in the tree,
+/// and labelled with its network namespace if it is not ours, or with the likely cause
+/// of unattributed traffic.
+fn row_label(row: &ProcessRow, netns: Option<&str>) -> String {
+    let marker = match (row.children, row.collapsed) {
+        (0, _) => "",
+        (_, true) => "▸ ",
+        (_, false) => "▾ ",
+    };
+    let netns = netns.map(|ns| format!(" [{ns}]")).unwrap_or_default();
+    let cause = match row.cause {
+        Some(ref cause) => format!(" ({cause})"),
+        None => String::new(),
+    };
+    format!(
+        "{}{marker}{}{netns}{cause}",
+        "  ".repeat(row.depth),
+        row.process.name
+    )
+}
+
+fn render_traffic_cell(
     frame: &mut Frame,
     rect: Rect,
-    row: &crate::display::ProcessRow,
-    unit_family: crate::display::BandwidthUnitFamily,
-    max_download: f64,
-    max_upload: f64,
+    column: Column,
+    traffic: &Traffic,
+    state: &UIState,
+    max: f64,
 ) {
-    let columns = split_columns(rect);
-    let name = truncate_to_width(&row.process.name, columns[0].width);
-    let down_rate = format!(
-        "{}/s",
-        DisplayBandwidth {
-            bandwidth: row.current_bytes_downloaded as f64,
-            unit_family,
+    let bandwidth = |bandwidth: u128| DisplayBandwidth {
+        bandwidth: bandwidth as f64,
+        unit_family: state.unit_family,
+    };
+    let rate = |bandwidth: f64| {
+        format!(
+            "{}/s",
+            DisplayBandwidth {
+                bandwidth,
+                unit_family: state.unit_family,
+            }
+        )
+    };
+    let text = match column {
+        Column::Down => format!("{}/s", bandwidth(traffic.current_bytes_downloaded)),
+        Column::Up => format!("{}/s", bandwidth(traffic.current_bytes_uploaded)),
+        Column::Combined => format!(
+            "{}/s",
+            bandwidth(traffic.current_bytes_downloaded + traffic.current_bytes_uploaded)
+        ),
+        Column::TotalDown => format!("{}", bandwidth(traffic.total_bytes_downloaded)),
+        Column::TotalUp => format!("{}", bandwidth(traffic.total_bytes_uploaded)),
+        Column::DownChart => {
+            let history = traffic.download_history;
+            render_history(frame, rect, history, max, Series::Download, state);
+            return mark_history_cursor(frame, rect, state, points_per_column(state.chart_style));
         }
-    );
-    let up_rate = format!(
-        "{}/s",
-        DisplayBandwidth {
-            bandwidth: row.current_bytes_uploaded as f64,
-            unit_family,
+        Column::UpChart => {
+            let history = traffic.upload_history;
+            render_history(frame, rect, history, max, Series::Upload, state);
+            return mark_history_cursor(frame, rect, state, points_per_column(state.chart_style));
         }
-    );
-    let total_down = format!(
-        "{}",
-        DisplayBandwidth {
-            bandwidth: row.total_bytes_downloaded as f64,
-            unit_family,
-        }
-    );
-    let total_up = format!(
-        "{}",
-        DisplayBandwidth {
-            bandwidth: row.total_bytes_uploaded as f64,
-            unit_family,
+        Column::PeakDown => rate(traffic.download_stats.peak),
+        Column::PeakUp => rate(traffic.upload_stats.peak),
+        Column::MeanDown => rate(traffic.download_stats.mean),
+        Column::MeanUp => rate(traffic.upload_stats.mean),
+        Column::P95Down => rate(traffic.download_stats.p95),
+        Column::P95Up => rate(traffic.upload_stats.p95),
+        Column::Active => display_duration(traffic.active_time),
+        Column::Name | Column::Pid | Column::User | Column::Connections => return,
+    };
+    frame.render_widget(Paragraph::new(text).alignment(Alignment::Right), rect);
+}
+
+/// Formats a duration in its two largest units, e.g. `3m05s` or `2d04h`.
+fn display_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        3600..86400 => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d{:02}h", seconds / 86400, seconds % 86400 / 3600),
+    }
+}
+
+/// The direction of the traffic a chart shows, which picks its colors.
+#[derive(Clone, Copy)]
+enum Series {
+    Download,
+    Upload,
+}
+
+impl Series {
+    fn gradient(self, theme: &Theme) -> (Color, Color) {
+        match self {
+            Series::Download => theme.download,
+            Series::Upload => theme.upload,
         }
-    );
+    }
+}
 
-    frame.render_widget(Paragraph::new(name), columns[0]);
-    frame.render_widget(
-        Paragraph::new(down_rate).alignment(Alignment::Right),
-        columns[1],
-    );
-    frame.render_widget(
-        Paragraph::new(up_rate).alignment(Alignment::Right),
-        columns[2],
-    );
-    frame.render_widget(
-        Paragraph::new(total_down).alignment(Alignment::Right),
-        columns[3],
-    );
-    frame.render_widget(
-        Paragraph::new(total_up).alignment(Alignment::Right),
-        columns[4],
-    );
+/// Draws a rate history in the style and on the scale chosen by the user. Charts are
+/// scaled against `global_max`, or their own peak if it is zero.
+fn render_history(
+    frame: &mut Frame,
+    rect: Rect,
+    history: &VecDeque<f64>,
+    global_max: f64,
+    series: Series,
+    state: &UIState,
+) {
+    let gradient = series.gradient(&state.theme);
+    let global_max = match state.chart_scale {
+        ChartScale::Global => global_max,
+        ChartScale::Row => 0.0,
+    };
+    let (history, global_max) = match state.log_scale {
+        true => (
+            Cow::Owned(history.iter().map(|value| value.ln_1p()).collect()),
+            global_max.ln_1p(),
+        ),
+        false => (Cow::Borrowed(history), global_max),
+    };
+    match state.chart_style {
+        ChartStyle::Bars => render_bar_chart(frame, rect, &history, global_max, gradient),
+        ChartStyle::Braille => render_line_chart(frame, rect, &history, global_max, gradient.0),
+    }
+}
 
-    render_bar_chart(
-        frame,
-        columns[5],
-        &row.download_history,
-        max_download,
-        Color::Cyan,
-    );
-    render_bar_chart(
-        frame,
-        columns[6],
-        &row.upload_history,
-        max_upload,
-        Color::Magenta,
-    );
+/// Highlights the column of a chart holding the tick the table is looking at, given
+/// how many ticks each column of the chart covers.
+fn mark_history_cursor(frame: &mut Frame, rect: Rect, state: &UIState, samples_per_column: usize) {
+    let Some(back) = state.history_cursor else {
+        return;
+    };
+    let column = back / samples_per_column.max(1);
+    if column >= rect.width as usize {
+        return;
+    }
+    let mark = Rect {
+        x: rect.right() - 1 - column as u16,
+        width: 1,
+        ..rect
+    };
+    frame
+        .buffer_mut()
+        .set_style(mark, Style::default().add_modifier(Modifier::REVERSED));
 }
 
-fn render_bar_chart(
+/// How many samples a chart shows per terminal column.
+fn points_per_column(chart_style: ChartStyle) -> usize {
+    match chart_style {
+        ChartStyle::Bars => 1,
+        ChartStyle::Braille => 2,
+    }
+}
+
+fn render_line_chart(
     frame: &mut Frame,
     rect: Rect,
     history: &VecDeque<f64>,
     global_max: f64,
     color: Color,
+) {
+    const CHART_HEADROOM: f64 = 1.1;
+
+    if rect.width == 0 || rect.height == 0 || history.is_empty() {
+        return;
+    }
+
+    let values = fixed_history_window(history, rect.width as usize * 2);
+    let scale_basis = match global_max > 0.0 {
+        true => global_max,
+        false => values.iter().copied().fold(0.0_f64, f64::max),
+    };
+    let scale_max = match scale_basis > 0.0 {
+        true => scale_basis * CHART_HEADROOM,
+        false => 1.0,
+    };
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, &value)| (index as f64, value.min(scale_max)))
+        .collect::<Vec<_>>();
+
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .x_axis(Axis::default().bounds([0.0, points.len().saturating_sub(1) as f64]))
+        .y_axis(Axis::default().bounds([0.0, scale_max]));
+    frame.render_widget(chart, rect);
+}
+
+fn render_bar_chart(
+    frame: &mut Frame,
+    rect: Rect,
+    history: &VecDeque<f64>,
+    global_max: f64,
+    gradient: (Color, Color),
 ) {
     if rect.width == 0 || rect.height == 0 {
         return;
     }
 
-    let (bars, max_value) = history_to_bars(history, rect.width as usize, global_max);
+    let max_ticks = TICKS_PER_ROW * rect.height as u64;
+    let (bars, max_value) = history_to_bars(
+        history,
+        rect.width as usize,
+        global_max,
+        max_ticks,
+        gradient,
+    );
     if bars.is_empty() {
         return;
     }
 
-    let _ = color;
     let group = BarGroup::default().bars(&bars);
     let chart = BarChart::default()
         .bar_width(1)
         .bar_gap(0)
         .group_gap(0)
-        .bar_style(Style::default().fg(CHART_COLOR_START))
+        .bar_style(Style::default().fg(gradient.0))
         .max(max_value)
         .data(group);
 
@@ -316,12 +1105,13 @@ fn history_to_bars(
     history: &VecDeque<f64>,
     target_len: usize,
     global_max: f64,
+    max_ticks: u64,
+    gradient: (Color, Color),
 ) -> (Vec<Bar<'static>>, u64) {
     const CHART_HEADROOM: f64 = 1.1;
-    const CHART_MAX_TICKS: u64 = 8;
 
     if history.is_empty() || target_len == 0 {
-        return (Vec::new(), CHART_MAX_TICKS);
+        return (Vec::new(), max_ticks);
     }
 
     let mut max_value = 0.0_f64;
@@ -355,10 +1145,9 @@ fn history_to_bars(
         .into_iter()
         .map(|value| {
             let ratio = (value / scale_max).clamp(0.0, 1.0);
-            let ticks =
-                ((ratio * (CHART_MAX_TICKS as f64 - 1.0)).ceil() as u64).clamp(1, CHART_MAX_TICKS);
-            let color_ratio = (ticks.saturating_sub(1)) as f64 / (CHART_MAX_TICKS - 1) as f64;
-            let color = gradient_color(color_ratio);
+            let ticks = ((ratio * (max_ticks as f64 - 1.0)).ceil() as u64).clamp(1, max_ticks);
+            let color_ratio = (ticks.saturating_sub(1)) as f64 / (max_ticks - 1) as f64;
+            let color = gradient_color(gradient, color_ratio);
             Bar::default()
                 .value(ticks)
                 .text_value(String::new())
@@ -366,19 +1155,33 @@ fn history_to_bars(
         })
         .collect();
 
-    (bars, CHART_MAX_TICKS)
+    (bars, max_ticks)
 }
 
-fn max_history_values(state: &UIState) -> (f64, f64) {
+/// Shrinks a history to fit the given number of bars, keeping the peak of each bucket.
+fn downsample_history(history: &VecDeque<f64>, target_len: usize) -> VecDeque<f64> {
+    if target_len == 0 || history.len() <= target_len {
+        return history.clone();
+    }
+    (0..target_len)
+        .map(|bucket| {
+            let start = bucket * history.len() / target_len;
+            let end = (bucket + 1) * history.len() / target_len;
+            history.range(start..end).copied().fold(0.0_f64, f64::max)
+        })
+        .collect()
+}
+
+fn max_history_values<'a>(rows: impl Iterator<Item = &'a Traffic<'a>>) -> (f64, f64) {
     let mut max_download = 0.0_f64;
     let mut max_upload = 0.0_f64;
-    for row in &state.process_rows {
-        for value in &row.download_history {
+    for traffic in rows {
+        for value in traffic.download_history {
             if *value > max_download {
                 max_download = *value;
             }
         }
-        for value in &row.upload_history {
+        for value in traffic.upload_history {
             if *value > max_upload {
                 max_upload = *value;
             }
@@ -387,23 +1190,6 @@ fn max_history_values(state: &UIState) -> (f64, f64) {
     (max_download, max_upload)
 }
 
-fn gradient_color(ratio: f64) -> Color {
-    let ratio = ratio.clamp(0.0, 1.0);
-    let (sr, sg, sb) = color_to_rgb(CHART_COLOR_START);
-    let (er, eg, eb) = color_to_rgb(CHART_COLOR_END);
-    let r = sr as f64 + (er as f64 - sr as f64) * ratio;
-    let g = sg as f64 + (eg as f64 - sg as f64) * ratio;
-    let b = sb as f64 + (eb as f64 - sb as f64) * ratio;
-    Color::Rgb(r.round() as u8, g.round() as u8, b.round() as u8)
-}
-
-fn color_to_rgb(color: Color) -> (u8, u8, u8) {
-    match color {
-        Color::Rgb(r, g, b) => (r, g, b),
-        _ => (0, 0, 0),
-    }
-}
-
 fn fixed_history_window(history: &VecDeque<f64>, target_len: usize) -> Vec<f64> {
     if target_len == 0 {
         return Vec::new();
@@ -418,36 +1204,11 @@ fn fixed_history_window(history: &VecDeque<f64>, target_len: usize) -> Vec<f64>
     }
 
     let mut out = Vec::with_capacity(target_len);
-    out.extend(std::iter::repeat(0.0).take(target_len - history_len));
+    out.extend(std::iter::repeat_n(0.0, target_len - history_len));
     out.extend(history.iter().copied());
     out
 }
 
-fn split_columns(rect: Rect) -> Vec<Rect> {
-    let constraints = [
-        Constraint::Length(24),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Length(12),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Length(12),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Length(12),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Length(12),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Min(10),
-        Constraint::Length(COLUMN_GAP),
-        Constraint::Min(10),
-    ];
-
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(rect);
-
-    chunks.iter().step_by(2).copied().collect()
-}
-
 fn truncate_to_width(text: &str, max_width: u16) -> String {
     if max_width == 0 {
         return String::new();
@@ -465,15 +1226,227 @@ fn truncate_to_width(text: &str, max_width: u16) -> String {
     out
 }
 
-fn render_footer(frame: &mut Frame, rect: Rect, paused: bool) {
-    let status = if paused { "Paused" } else { "Live" };
-    let content = format!("{status} | Press <SPACE> to toggle | Press <Q> to quit");
+/// Renders the signal picker or confirmation dialog over the middle of the screen.
+fn render_signal_prompt(frame: &mut Frame, area: Rect, prompt: &SignalPrompt, theme: &Theme) {
+    let (title, lines) = match prompt {
+        SignalPrompt::Picker { target, cursor } => {
+            let lines = Signal::ALL
+                .iter()
+                .enumerate()
+                .map(|(index, signal)| {
+                    let style = match index == *cursor {
+                        true => theme.selection,
+                        false => Style::default(),
+                    };
+                    Line::styled(format!(" {signal}"), style)
+                })
+                .collect::<Vec<_>>();
+            (format!("Signal {} ({})", target.name, target.pid), lines)
+        }
+        SignalPrompt::Confirm { target, signal } => {
+            let question = format!(" Send {signal} to {} ({})? [y/N]", target.name, target.pid);
+            ("Confirm".to_string(), vec![Line::raw(question)])
+        }
+    };
+    let width = lines
+        .iter()
+        .map(Line::width)
+        .chain([title.len()])
+        .max()
+        .unwrap_or_default() as u16
+        + 4;
+    let height = lines.len() as u16 + 2;
+    let rect = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+    frame.render_widget(Clear, rect);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title)),
+        rect,
+    );
+}
+
+fn render_help(frame: &mut Frame, area: Rect, offset: usize, state: &UIState) {
+    let entries = state
+        .keymap
+        .entries()
+        .map(|(action, keys)| {
+            let keys = match keys.is_empty() {
+                true => "(unbound)".to_string(),
+                false => keys.iter().map(ToString::to_string).join(", "),
+            };
+            (keys, action.description())
+        })
+        .collect::<Vec<_>>();
+    let keys_width = entries
+        .iter()
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or_default();
+    let lines = entries
+        .iter()
+        .map(|(keys, description)| {
+            Line::from(vec![
+                Span::styled(format!(" {keys:<keys_width$}  "), state.theme.table_header),
+                Span::raw(format!("{description} ")),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let title = "Keys";
+    let width = lines.iter().map(Line::width).max().unwrap_or_default() as u16 + 2;
+    let height = lines.len() as u16 + 2;
+    let rect = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+    // keep the last line at the bottom edge rather than scrolling past it
+    let offset = offset.min(
+        lines
+            .len()
+            .saturating_sub(rect.height.saturating_sub(2) as usize),
+    );
+    frame.render_widget(Clear, rect);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .scroll((offset as u16, 0))
+            .block(Block::bordered().title(title)),
+        rect,
+    );
+}
+
+fn render_footer(frame: &mut Frame, rect: Rect, paused: bool, state: &UIState) {
+    let group = group_title(state.group_by);
+    let sort = state
+        .sort_by
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default();
+    let filter = &state.filter_text;
+    let key = |action| state.keymap.hint(action);
+    let status = match paused {
+        true => format!(
+            "Paused | {}/{} look back",
+            key(Action::Earlier),
+            key(Action::Later)
+        ),
+        false => "Live".to_string(),
+    };
+    let content = match (&state.detail, state.editing_filter) {
+        (_, true) => format!("/{filter}█ | <Enter> apply | <Esc> clear"),
+        (Some(_), false) => format!(
+            "{status} | {} help | {} pause | {} signal | {} back | {} quit",
+            key(Action::Help),
+            key(Action::Pause),
+            key(Action::Signal),
+            key(Action::Back),
+            key(Action::Quit),
+        ),
+        (None, false) => {
+            let filter = match filter.is_empty() {
+                true => String::new(),
+                false => format!(" | Filter: {filter} ({} to clear)", key(Action::Back)),
+            };
+            format!(
+                "{status} | {} help | {} pause | {} switch table | {} details | {} group: {group} | {} sort: {sort} | {} reverse | {} filter | {} quit{filter}",
+                key(Action::Help),
+                key(Action::Pause),
+                key(Action::NextTable),
+                key(Action::Open),
+                key(Action::Group),
+                key(Action::Sort),
+                key(Action::Reverse),
+                key(Action::Filter),
+                key(Action::Quit),
+            )
+        }
+    };
     let footer = Paragraph::new(content)
-        .style(
-            Style::default()
-                .fg(Color::Gray)
-                .add_modifier(Modifier::BOLD),
-        )
+        .style(state.theme.footer)
         .alignment(Alignment::Left);
     frame.render_widget(footer, rect);
 }
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_snapshot;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::{
+        display::{UnknownCause, UnknownTraffic},
+        network::Protocol,
+    };
+
+    #[test]
+    fn label_rows() {
+        let row = ProcessRow {
+            process: ProcessInfo::new("<UNKNOWN>", 0),
+            children: 2,
+            ..Default::default()
+        };
+        assert_eq!(row_label(&row, None), "▾ <UNKNOWN>");
+
+        // breakdown rows are named after the traffic, as the UI state does
+        let traffic = UnknownTraffic {
+            protocol: Protocol::Tcp,
+            local_port: 2049,
+            remote_ip: "10.0.0.1".parse().unwrap(),
+        };
+        let breakdown = ProcessRow {
+            process: ProcessInfo::new(&traffic.to_string(), 0),
+            depth: 1,
+            cause: Some(UnknownCause::Kernel),
+            ..Default::default()
+        };
+        assert_eq!(
+            row_label(&breakdown, None),
+            "  tcp/2049 ⇄ 10.0.0.1 (kernel)"
+        );
+
+        let foreign = ProcessRow {
+            process: ProcessInfo::new("nginx", 42),
+            ..Default::default()
+        };
+        assert_eq!(row_label(&foreign, Some("web")), "nginx [web]");
+    }
+
+    /// Renders the download and upload charts of a history side by side.
+    fn render_charts(state: &UIState) -> String {
+        let history = VecDeque::from([0.0, 100.0, 400.0, 900.0, 300.0, 50.0, 0.0, 700.0]);
+        let mut terminal = Terminal::new(TestBackend::new(16, 3)).unwrap();
+        terminal
+            .draw(|frame| {
+                let [download, upload] =
+                    Layout::horizontal([Constraint::Fill(1); 2]).areas(frame.area());
+                render_history(frame, download, &history, 0.0, Series::Download, state);
+                render_history(frame, upload, &history, 0.0, Series::Upload, state);
+            })
+            .unwrap();
+        format!("{:?}", terminal.backend().buffer())
+    }
+
+    #[test]
+    fn render_bar_charts() {
+        let state = UIState::default();
+        assert_snapshot!(render_charts(&state));
+    }
+
+    #[test]
+    fn render_line_charts() {
+        let mut state = UIState::default();
+        state.chart_style = ChartStyle::Braille;
+        assert_snapshot!(render_charts(&state));
+    }
+
+    #[test]
+    fn render_log_scale_charts() {
+        let mut state = UIState::default();
+        state.log_scale = true;
+        assert_snapshot!(render_charts(&state));
+    }
+}
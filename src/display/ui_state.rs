@@ -1,19 +1,50 @@
 use std::{
-    cmp,
-    collections::{HashMap, HashSet, VecDeque},
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    cmp::Reverse,
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+    hash::Hash,
+    mem,
+    net::IpAddr,
+    time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local};
 use log::warn;
+use regex::{Regex, RegexBuilder};
+use strum::IntoEnumIterator;
 
 use crate::{
-    display::BandwidthUnitFamily,
-    network::{LocalSocket, Utilization},
-    os::ProcessInfo,
+    alerts::{self, Alert, AlertEvent},
+    cli::{ChartScale, ChartStyle, Column, GroupBy, SortBy, UsageKind},
+    display::{
+        compare_rows, BandwidthUnitFamily, OrphanContext, Table, Theme, UnknownCause,
+        UnknownTraffic,
+    },
+    keymap::{Action, Keymap},
+    network::{Connection, LocalSocket, Resolver, Utilization},
+    os::{is_running, send_signal, ProcessInfo, ProcessMeta, Signal},
+    usage::UsageKey,
+    OpenSockets,
 };
 
-static HISTORY_LENGTH: usize = 100;
-static MAX_BANDWIDTH_ITEMS: usize = 1000;
+/// The refresh intervals stepped through at runtime.
+static INTERVALS: [Duration; 8] = [
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+/// The history lengths stepped through at runtime, in refreshes.
+static HISTORY_LENGTHS: [usize; 6] = [50, 100, 200, 500, 1000, 3600];
+/// How long the outcome of an action stays on screen.
+static STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+/// The most totals kept of connections and remote addresses that went idle.
+static MAX_IDLE_TOTALS: usize = 10_000;
+/// Guards against cycles in stale parent links when walking up the process tree.
+static MAX_TREE_DEPTH: usize = 256;
 
 #[derive(Clone, Default)]
 pub struct NetworkData {
@@ -21,15 +52,95 @@ pub struct NetworkData {
     pub total_bytes_uploaded: u128,
 }
 
+impl NetworkData {
+    /// Turns the bytes of a tick into bytes per second.
+    fn per_second(&self, seconds: f64) -> NetworkData {
+        NetworkData {
+            total_bytes_downloaded: (self.total_bytes_downloaded as f64 / seconds).round() as u128,
+            total_bytes_uploaded: (self.total_bytes_uploaded as f64 / seconds).round() as u128,
+        }
+    }
+}
+
+/// How the traffic of a tick is added to the histories.
+#[derive(Copy, Clone)]
+struct Tick {
+    /// How long the traffic was collected for.
+    seconds: f64,
+    /// The weight of this tick's rate in the current rate, 1 if unsmoothed.
+    weight: f64,
+    history_length: usize,
+}
+
+impl Tick {
+    fn new(elapsed: Duration, smoothing: Option<Duration>, history_length: usize) -> Self {
+        let seconds = elapsed.as_secs_f64();
+        // an exponentially weighted moving average with this time constant, which
+        // weighs longer ticks more
+        let weight = match smoothing {
+            Some(smoothing) if !smoothing.is_zero() => {
+                1.0 - (-seconds / smoothing.as_secs_f64()).exp()
+            }
+            _ => 1.0,
+        };
+        Tick {
+            seconds,
+            weight,
+            history_length,
+        }
+    }
+
+    /// Blends the rate of this tick into the current rate.
+    fn smooth(&self, current: u128, rate: u128) -> u128 {
+        (self.weight * rate as f64 + (1.0 - self.weight) * current as f64).round() as u128
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct ProcessHistory {
+    pub current_bytes_downloaded: u128,
+    pub current_bytes_uploaded: u128,
     pub total_bytes_downloaded: u128,
     pub total_bytes_uploaded: u128,
     pub download_history: VecDeque<f64>,
     pub upload_history: VecDeque<f64>,
+    /// Number of ticks since the last one with any traffic.
+    pub idle_ticks: usize,
+    /// How long there was any traffic, since first seen.
+    pub active_time: Duration,
+    /// Number of ticks since first seen, which may be fewer than the history holds.
+    pub samples: usize,
 }
 
-#[derive(Clone)]
+/// Peak, mean and 95th percentile of a rate history.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateStats {
+    pub peak: f64,
+    pub mean: f64,
+    pub p95: f64,
+}
+
+impl RateStats {
+    /// Computes the statistics of the last `samples` values of a history, leaving out
+    /// the padding from before the traffic was first seen.
+    pub fn of(history: &VecDeque<f64>, samples: usize) -> Self {
+        let skipped = history.len().saturating_sub(samples);
+        let mut sorted = history.iter().skip(skipped).copied().collect::<Vec<_>>();
+        if sorted.is_empty() {
+            return RateStats::default();
+        }
+        sorted.sort_unstable_by(f64::total_cmp);
+        // nearest rank, so that the percentile is one of the samples
+        let rank = (sorted.len() as f64 * 0.95).ceil() as usize;
+        RateStats {
+            peak: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p95: sorted[rank.saturating_sub(1)],
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct ProcessRow {
     pub process: ProcessInfo,
     pub current_bytes_downloaded: u128,
@@ -38,6 +149,164 @@ pub struct ProcessRow {
     pub total_bytes_uploaded: u128,
     pub download_history: VecDeque<f64>,
     pub upload_history: VecDeque<f64>,
+    /// Nesting level in tree mode.
+    pub depth: usize,
+    /// Number of direct children in tree mode.
+    pub children: usize,
+    /// Whether the children of this row are hidden in tree mode.
+    pub collapsed: bool,
+    /// Set on the breakdown rows of unattributed traffic.
+    pub cause: Option<UnknownCause>,
+    /// Number of connections within the history window.
+    pub connections: usize,
+    /// Whether the process has exited, and is only kept around to be shown as such.
+    pub exited: bool,
+    /// How long there was any traffic. For groups, that of their busiest member.
+    pub active_time: Duration,
+    /// Whether an alert is firing for the process, or one of the group.
+    pub alerting: bool,
+    /// Number of ticks since first seen. For groups, that of their oldest member.
+    pub samples: usize,
+    pub download_stats: RateStats,
+    pub upload_stats: RateStats,
+}
+
+impl ProcessRow {
+    /// Adds the traffic of another row to this one, sample by sample.
+    fn merge(&mut self, other: &ProcessRow) {
+        self.current_bytes_downloaded += other.current_bytes_downloaded;
+        self.current_bytes_uploaded += other.current_bytes_uploaded;
+        self.total_bytes_downloaded += other.total_bytes_downloaded;
+        self.total_bytes_uploaded += other.total_bytes_uploaded;
+        for (value, other) in self
+            .download_history
+            .iter_mut()
+            .zip(&other.download_history)
+        {
+            *value += other;
+        }
+        for (value, other) in self.upload_history.iter_mut().zip(&other.upload_history) {
+            *value += other;
+        }
+        self.active_time = self.active_time.max(other.active_time);
+        self.alerting |= other.alerting;
+        self.samples = self.samples.max(other.samples);
+    }
+}
+
+#[derive(Clone)]
+pub struct ConnectionRow {
+    pub connection: Connection,
+    pub process: ProcessInfo,
+    pub current_bytes_downloaded: u128,
+    pub current_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+    pub download_history: VecDeque<f64>,
+    pub upload_history: VecDeque<f64>,
+    pub active_time: Duration,
+    pub download_stats: RateStats,
+    pub upload_stats: RateStats,
+}
+
+#[derive(Clone)]
+pub struct RemoteRow {
+    pub ip: IpAddr,
+    /// Number of local processes that talked to this address within the history window.
+    pub processes: usize,
+    pub current_bytes_downloaded: u128,
+    pub current_bytes_uploaded: u128,
+    pub total_bytes_downloaded: u128,
+    pub total_bytes_uploaded: u128,
+    pub download_history: VecDeque<f64>,
+    pub upload_history: VecDeque<f64>,
+    pub active_time: Duration,
+    pub download_stats: RateStats,
+    pub upload_stats: RateStats,
+}
+
+/// The cursor of a table, which follows the row it is on across refreshes, and how far
+/// the table is scrolled.
+#[derive(Clone, Debug)]
+pub struct TableCursor<K> {
+    /// Index of the selected row.
+    pub selected: usize,
+    /// The row under the cursor.
+    followed: Option<K>,
+    /// Index of the first visible row.
+    pub offset: usize,
+}
+
+impl<K> Default for TableCursor<K> {
+    fn default() -> Self {
+        TableCursor {
+            selected: 0,
+            followed: None,
+            offset: 0,
+        }
+    }
+}
+
+impl<K: PartialEq> TableCursor<K> {
+    /// Moves the cursor to the given row, or to the closest one that exists.
+    fn select<R>(&mut self, index: usize, rows: &[R], key: impl Fn(&R) -> K) {
+        self.selected = index.min(rows.len().saturating_sub(1));
+        self.followed = rows.get(self.selected).map(key);
+    }
+
+    /// Keeps the cursor on the same row after the rows were rebuilt.
+    fn follow<R>(&mut self, rows: &[R], key: impl Fn(&R) -> K) {
+        let followed = self
+            .followed
+            .as_ref()
+            .and_then(|followed| rows.iter().position(|row| key(row) == *followed));
+        self.select(followed.unwrap_or(self.selected), rows, key);
+    }
+
+    /// Scrolls so that the cursor is visible, given how many of the rows fit.
+    fn scroll_into_view(&mut self, rows: usize, page_size: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if page_size > 0 && self.selected >= self.offset + page_size {
+            self.offset = self.selected + 1 - page_size;
+        }
+        self.offset = self.offset.min(rows.saturating_sub(page_size));
+    }
+}
+
+/// A signal about to be sent to a process, pending the user's choice or confirmation.
+#[derive(Clone, Debug)]
+pub enum SignalPrompt {
+    /// Choosing which signal to send, from `Signal::ALL`.
+    Picker { target: ProcessInfo, cursor: usize },
+    /// Waiting for the user to confirm.
+    Confirm { target: ProcessInfo, signal: Signal },
+}
+
+/// The rates of one tick, kept so that the history can be looked back on while paused.
+struct Snapshot {
+    at: DateTime<Local>,
+    processes: HashMap<ProcessInfo, NetworkData>,
+    unknown: HashMap<UnknownTraffic, NetworkData>,
+}
+
+struct ConnectionHistory {
+    process: ProcessInfo,
+    history: ProcessHistory,
+}
+
+/// History of a slice of traffic that could not be attributed to a process.
+struct UnknownHistory {
+    cause: UnknownCause,
+    history: ProcessHistory,
+}
+
+/// The traffic of a tick, attributed to the processes owning it.
+struct TickTraffic {
+    /// Each connection with its traffic and owner, if known.
+    connections: Vec<(Connection, NetworkData, Option<ProcessInfo>)>,
+    /// The traffic of each process, and of `<UNKNOWN>`.
+    processes: HashMap<ProcessInfo, NetworkData>,
 }
 
 #[derive(Default)]
@@ -46,168 +315,1933 @@ pub struct UIState {
     pub interface_name: Option<String>,
     pub total_bytes_downloaded: u128,
     pub total_bytes_uploaded: u128,
+    /// Rates of all traffic, summed per tick.
+    pub total_history: ProcessHistory,
+    /// Whether the total bandwidth chart is shown above the table.
+    pub show_total_chart: bool,
+    pub chart_style: ChartStyle,
+    pub chart_scale: ChartScale,
+    /// Whether charts are drawn on a logarithmic scale.
+    pub log_scale: bool,
+    /// How often the display refreshes.
+    pub interval: Duration,
+    /// Time constant of the moving average of current rates, if they are smoothed.
+    pub smoothing: Option<Duration>,
+    /// How many ticks the histories keep.
+    pub history_length: usize,
+    /// The most rows kept in each table.
+    pub max_rows: usize,
+    /// How many idle ticks an exited process is kept for.
+    pub evict_after: usize,
+    /// The most processes kept, the longest idle ones being dropped first.
+    pub max_processes: usize,
+    /// Whether exited processes are shown in a section of their own while they are kept.
+    pub show_exited: bool,
+    /// Whether a process that replaces an exited one with the same executable takes over
+    /// its history.
+    pub merge_restarts: bool,
+    /// Alert rules and the traffic they have seen.
+    pub alerts: Vec<Alert>,
+    /// The processes whose traffic keeps an alert firing, as of the latest tick.
+    alerting: HashSet<ProcessInfo>,
+    /// Alerts fired by the latest update.
+    pub fired_alerts: Vec<AlertEvent>,
+    /// Whether traffic is tallied by process name and unit, to be recorded.
+    pub record_usage: bool,
+    /// Traffic tallied since it was last taken to be recorded.
+    usage: HashMap<UsageKey, NetworkData>,
+    /// Processes of the history that have exited.
+    exited: HashSet<ProcessInfo>,
+    /// How many ticks back the table is looking, while paused.
+    pub history_cursor: Option<usize>,
+    /// The rates of every tick within the history window, oldest first.
+    snapshots: VecDeque<Snapshot>,
     pub unit_family: BandwidthUnitFamily,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// Scroll offset of the help overlay, while it is shown.
+    pub help: Option<usize>,
+    /// Columns of the tables, as chosen by the user.
+    pub columns: Vec<Column>,
+    pub group_by: GroupBy,
+    pub sort_by: SortBy,
+    /// Whether the sort order is reversed.
+    pub reverse: bool,
+    /// Names of known users, by UID.
+    pub user_names: HashMap<u32, String>,
+    /// If set, only traffic of sockets owned by this UID is accounted for.
+    pub only_uid: Option<u32>,
+    /// The monitored network namespace, if not our own. `all` in all-namespaces mode.
+    pub netns: Option<String>,
+    /// Addresses of the monitored interfaces, used to detect forwarded traffic.
+    pub local_ips: HashSet<IpAddr>,
+    /// Resolves remote addresses, unless disabled.
+    pub resolver: Option<Resolver>,
+    /// Host names of remote addresses, as far as they could be resolved.
+    pub ip_to_host: HashMap<IpAddr, String>,
     pub process_rows: Vec<ProcessRow>,
+    pub connection_rows: Vec<ConnectionRow>,
+    pub remote_rows: Vec<RemoteRow>,
+    /// Narrows all tables down to rows matching it, as typed after `/`.
+    pub filter: Option<Regex>,
+    /// The text of the filter, as typed.
+    pub filter_text: String,
+    /// Whether the filter is being typed.
+    pub editing_filter: bool,
+    pub signal_prompt: Option<SignalPrompt>,
+    /// The outcome of the last action, and when it happened.
+    status: Option<(Instant, String)>,
+    pub process_cursor: TableCursor<ProcessInfo>,
+    pub connection_cursor: TableCursor<Connection>,
+    pub remote_cursor: TableCursor<IpAddr>,
+    /// Number of rows that fit in a table, as of the last draw.
+    pub page_size: usize,
+    /// The process whose details are shown instead of the tables.
+    pub detail: Option<ProcessInfo>,
     process_history: HashMap<ProcessInfo, ProcessHistory>,
+    /// The last known attributes of every running process, and of every process in
+    /// `process_history`.
+    process_meta: HashMap<ProcessInfo, ProcessMeta>,
+    /// Rows whose children are hidden in tree mode.
+    collapsed: HashSet<ProcessInfo>,
+    /// Connections seen within the history window.
+    connection_history: HashMap<Connection, ConnectionHistory>,
+    /// Traffic by remote address, across all processes.
+    remote_history: HashMap<IpAddr, ProcessHistory>,
+    /// Totals of connections that went idle, resumed if they show up again.
+    connection_totals: HashMap<Connection, NetworkData>,
+    /// Totals of remote addresses that went idle, resumed if they show up again.
+    remote_totals: HashMap<IpAddr, NetworkData>,
+    /// Breakdown of the traffic of the `<UNKNOWN>` row.
+    unknown_history: HashMap<UnknownTraffic, UnknownHistory>,
+    /// Socket owners as of the previous update, used to detect exited processes.
+    previous_sockets: HashMap<LocalSocket, ProcessInfo>,
     /// Used for reducing logging noise.
     known_orphan_sockets: VecDeque<LocalSocket>,
 }
 
 impl UIState {
-    pub fn update(
-        &mut self,
-        connections_to_procs: HashMap<LocalSocket, ProcessInfo>,
-        network_utilization: Utilization,
-    ) {
+    /// Checks the alert rules against the traffic of a tick and accounts it towards the
+    /// recorded usage, both of which go on while the display is paused.
+    pub fn account(&mut self, open_sockets: &OpenSockets, network_utilization: &Utilization) {
+        if self.alerts.is_empty() && !self.record_usage {
+            return;
+        }
+        let TickTraffic { processes, .. } =
+            self.traffic_by_process(open_sockets, network_utilization);
+        if !self.alerts.is_empty() {
+            let elapsed = match network_utilization.elapsed.is_zero() {
+                true => self.interval,
+                false => network_utilization.elapsed,
+            };
+            let total = processes
+                .values()
+                .fold(NetworkData::default(), |sum, data| NetworkData {
+                    total_bytes_downloaded: sum.total_bytes_downloaded
+                        + data.total_bytes_downloaded,
+                    total_bytes_uploaded: sum.total_bytes_uploaded + data.total_bytes_uploaded,
+                });
+            self.check_alerts(&processes, &total, elapsed.as_secs_f64());
+        }
+        if self.record_usage {
+            self.tally_usage(&processes, &open_sockets.processes);
+        }
+    }
+
+    /// Attributes the traffic of a tick to the process owning each connection, or
+    /// `<UNKNOWN>`, leaving out that of other users with `--only-mine`.
+    fn traffic_by_process(
+        &self,
+        open_sockets: &OpenSockets,
+        network_utilization: &Utilization,
+    ) -> TickTraffic {
+        let mut connections = Vec::new();
         let mut processes: HashMap<ProcessInfo, NetworkData> = HashMap::new();
+        for (connection, connection_info) in &network_utilization.connections {
+            let proc_info = socket_owner(
+                &connection.local_socket,
+                &open_sockets.sockets_to_procs,
+                &open_sockets.netns_sockets,
+            );
+            if let Some(uid) = self.only_uid {
+                let owner = proc_info
+                    .and_then(|proc_info| open_sockets.processes.get(proc_info))
+                    .and_then(|meta| meta.uid);
+                if owner != Some(uid) {
+                    continue;
+                }
+            }
+            let data = NetworkData {
+                total_bytes_downloaded: connection_info.total_bytes_downloaded,
+                total_bytes_uploaded: connection_info.total_bytes_uploaded,
+            };
+            let data_for_process = processes
+                .entry(proc_info.cloned().unwrap_or_else(unknown_process))
+                .or_default();
+            data_for_process.total_bytes_downloaded += data.total_bytes_downloaded;
+            data_for_process.total_bytes_uploaded += data.total_bytes_uploaded;
+            connections.push((*connection, data, proc_info.cloned()));
+        }
+        TickTraffic {
+            connections,
+            processes,
+        }
+    }
+
+    pub fn update(&mut self, open_sockets: OpenSockets, network_utilization: Utilization) {
+        let elapsed = match network_utilization.elapsed.is_zero() {
+            true => self.interval,
+            false => network_utilization.elapsed,
+        };
+        let tick = Tick::new(elapsed, self.smoothing, self.history_length);
+        let TickTraffic {
+            connections: attributed,
+            processes,
+        } = self.traffic_by_process(&open_sockets, &network_utilization);
+        let OpenSockets {
+            sockets_to_procs: connections_to_procs,
+            netns_sockets,
+            orphan_sockets,
+            processes: process_meta,
+        } = open_sockets;
+        let running = process_meta.keys().cloned().collect();
+        let lookalikes = netns_sockets
+            .values()
+            .chain([&connections_to_procs])
+            .flatten()
+            .map(|(socket, proc_info)| ((socket.port, socket.protocol), proc_info))
+            .collect();
+        let orphan_context = OrphanContext {
+            local_ips: &self.local_ips,
+            orphan_sockets: &orphan_sockets,
+            previous_sockets: &self.previous_sockets,
+            running: &running,
+            lookalikes: &lookalikes,
+        };
+        self.process_meta.retain(|proc_info, _| {
+            process_meta.contains_key(proc_info) || self.process_history.contains_key(proc_info)
+        });
+        for (proc_info, mut meta) in process_meta {
+            // the executable may no longer be readable, e.g. while the process exits
+            if meta.exe.is_none() {
+                meta.exe = self
+                    .process_meta
+                    .get(&proc_info)
+                    .and_then(|known| known.exe.clone());
+            }
+            self.process_meta.insert(proc_info, meta);
+        }
+
+        let mut unknown: HashMap<UnknownTraffic, (UnknownCause, NetworkData)> = HashMap::new();
+        let mut connections: HashMap<Connection, (ProcessInfo, NetworkData)> = HashMap::new();
+        let mut remotes: HashMap<IpAddr, NetworkData> = HashMap::new();
         let mut total_bytes_downloaded: u128 = 0;
         let mut total_bytes_uploaded: u128 = 0;
 
-        for (connection, connection_info) in &network_utilization.connections {
-            total_bytes_downloaded += connection_info.total_bytes_downloaded;
-            total_bytes_uploaded += connection_info.total_bytes_uploaded;
-
+        for (connection, data, proc_info) in attributed {
             let local_socket = connection.local_socket;
-            let proc_info = get_proc_info(&connections_to_procs, &local_socket);
+            if proc_info.is_none() {
+                let cause = UnknownCause::classify(&connection, &orphan_context);
 
-            if proc_info.is_none() && !self.known_orphan_sockets.contains(&local_socket) {
-                self.known_orphan_sockets.push_front(local_socket);
-                self.known_orphan_sockets.truncate(10_000);
+                if !self.known_orphan_sockets.contains(&local_socket) {
+                    self.known_orphan_sockets.push_front(local_socket);
+                    self.known_orphan_sockets.truncate(10_000);
 
-                match connections_to_procs
-                    .iter()
-                    .find(|(&LocalSocket { port, protocol, .. }, _)| {
-                        port == local_socket.port && protocol == local_socket.protocol
-                    })
-                    .and_then(|(local_conn_lookalike, info)| {
-                        network_utilization
-                            .connections
-                            .keys()
-                            .find(|conn| &conn.local_socket == local_conn_lookalike)
-                            .map(|conn| (conn, info))
-                    }) {
-                    Some((lookalike, proc_info)) => {
-                        warn!(
-                            r#""{0}" owns a similar looking connection, but its local ip doesn't match."#,
-                            proc_info.name
-                        );
-                        warn!("Looking for: {connection:?}; found: {lookalike:?}");
-                    }
-                    None => {
-                        warn!("Cannot determine which process owns {connection:?}");
-                    }
-                };
+                    match cause {
+                        UnknownCause::Lookalike(ref name) => {
+                            warn!(
+                                r#""{name}" owns a similar looking connection, but its local ip doesn't match."#
+                            );
+                            warn!("Looking for: {connection:?}");
+                        }
+                        _ => {
+                            warn!("Cannot determine which process owns {connection:?} ({cause})");
+                        }
+                    };
+                }
+
+                let (_, data_for_unknown) = unknown
+                    .entry((&connection).into())
+                    .or_insert_with(|| (cause, NetworkData::default()));
+                data_for_unknown.total_bytes_downloaded += data.total_bytes_downloaded;
+                data_for_unknown.total_bytes_uploaded += data.total_bytes_uploaded;
             }
 
-            let proc_info = proc_info
-                .cloned()
-                .unwrap_or_else(|| ProcessInfo::new("<UNKNOWN>", 0));
-            let data_for_process = processes.entry(proc_info).or_default();
+            total_bytes_downloaded += data.total_bytes_downloaded;
+            total_bytes_uploaded += data.total_bytes_uploaded;
 
-            data_for_process.total_bytes_downloaded += connection_info.total_bytes_downloaded;
-            data_for_process.total_bytes_uploaded += connection_info.total_bytes_uploaded;
+            let data_for_remote = remotes.entry(connection.remote_socket.ip).or_default();
+            data_for_remote.total_bytes_downloaded += data.total_bytes_downloaded;
+            data_for_remote.total_bytes_uploaded += data.total_bytes_uploaded;
+
+            let proc_info = proc_info.unwrap_or_else(unknown_process);
+            connections.insert(connection, (proc_info, data));
         }
 
         self.total_bytes_downloaded += total_bytes_downloaded;
         self.total_bytes_uploaded += total_bytes_uploaded;
+        push_sample(
+            &mut self.total_history,
+            &NetworkData {
+                total_bytes_downloaded,
+                total_bytes_uploaded,
+            },
+            tick,
+        );
 
+        self.merge_restarts(&processes, &running);
         let mut updated_processes = HashSet::new();
         for (proc_info, data) in &processes {
             updated_processes.insert(proc_info.clone());
             let history = self.process_history.entry(proc_info.clone()).or_default();
-            ensure_full_history(history);
-            history.total_bytes_downloaded += data.total_bytes_downloaded;
-            history.total_bytes_uploaded += data.total_bytes_uploaded;
-            history
-                .download_history
-                .push_back(data.total_bytes_downloaded as f64);
-            history
-                .upload_history
-                .push_back(data.total_bytes_uploaded as f64);
-            trim_history(history);
+            push_sample(history, data, tick);
         }
 
         for (proc_info, history) in self.process_history.iter_mut() {
             if !updated_processes.contains(proc_info) {
-                ensure_full_history(history);
-                history.download_history.push_back(0.0);
-                history.upload_history.push_back(0.0);
-                trim_history(history);
+                push_sample(history, &NetworkData::default(), tick);
             }
         }
 
-        let mut rows = self
-            .process_history
+        self.evict_processes(&running);
+
+        for (traffic, unknown_history) in self.unknown_history.iter_mut() {
+            if !unknown.contains_key(traffic) {
+                push_sample(&mut unknown_history.history, &NetworkData::default(), tick);
+            }
+        }
+        for (traffic, (cause, data)) in unknown {
+            let unknown_history =
+                self.unknown_history
+                    .entry(traffic)
+                    .or_insert_with(|| UnknownHistory {
+                        cause: cause.clone(),
+                        history: ProcessHistory::default(),
+                    });
+            unknown_history.cause = cause;
+            push_sample(&mut unknown_history.history, &data, tick);
+        }
+        // the rates as shown, which are smoothed, so that looking back shows the same
+        self.snapshots.push_back(Snapshot {
+            at: Local::now(),
+            processes: current_rates(&self.process_history, |history| history),
+            unknown: current_rates(&self.unknown_history, |unknown_history| {
+                &unknown_history.history
+            }),
+        });
+        while self.snapshots.len() > self.history_length {
+            self.snapshots.pop_front();
+        }
+        // the breakdown is only kept for as long as it shows up in the charts
+        self.unknown_history
+            .retain(|_, unknown_history| is_active(&unknown_history.history));
+
+        for (connection, connection_history) in self.connection_history.iter_mut() {
+            if !connections.contains_key(connection) {
+                push_sample(
+                    &mut connection_history.history,
+                    &NetworkData::default(),
+                    tick,
+                );
+            }
+        }
+        for (connection, (process, data)) in connections {
+            if let Some(ref mut resolver) = self.resolver {
+                resolver.resolve(connection.remote_socket.ip);
+            }
+            let connection_totals = &mut self.connection_totals;
+            let connection_history = self
+                .connection_history
+                .entry(connection)
+                .or_insert_with_key(|connection| ConnectionHistory {
+                    process: process.clone(),
+                    history: resume_history(connection_totals.remove(connection)),
+                });
+            connection_history.process = process;
+            push_sample(&mut connection_history.history, &data, tick);
+        }
+        retire_idle(
+            &mut self.connection_history,
+            &mut self.connection_totals,
+            |connection_history| &connection_history.history,
+        );
+
+        for (ip, history) in self.remote_history.iter_mut() {
+            if !remotes.contains_key(ip) {
+                push_sample(history, &NetworkData::default(), tick);
+            }
+        }
+        for (ip, data) in remotes {
+            let history = self
+                .remote_history
+                .entry(ip)
+                .or_insert_with(|| resume_history(self.remote_totals.remove(&ip)));
+            push_sample(history, &data, tick);
+        }
+        retire_idle(
+            &mut self.remote_history,
+            &mut self.remote_totals,
+            |history| history,
+        );
+
+        // host names are only kept for as long as their address shows up
+        let shown = self
+            .remote_history
+            .keys()
+            .copied()
+            .chain(
+                self.connection_history
+                    .keys()
+                    .map(|connection| connection.remote_socket.ip),
+            )
+            .collect::<HashSet<_>>();
+        if let Some(ref mut resolver) = self.resolver {
+            self.ip_to_host.extend(resolver.take_resolved());
+            resolver.retain(|ip| shown.contains(ip));
+        }
+        self.ip_to_host.retain(|ip, _| shown.contains(ip));
+
+        self.previous_sockets = connections_to_procs;
+        self.refresh_rows();
+    }
+
+    /// Switches to the next aggregation mode and regroups the existing history.
+    pub fn cycle_group_by(&mut self) {
+        let next = GroupBy::iter()
+            .cycle()
+            .skip_while(|group_by| *group_by != self.group_by)
+            .nth(1)
+            .unwrap_or_default();
+        self.group_by = next;
+        self.refresh_rows();
+    }
+
+    /// Switches to the next sort column and re-sorts the rows.
+    pub fn cycle_sort_by(&mut self) {
+        let next = SortBy::iter()
+            .cycle()
+            .skip_while(|sort_by| *sort_by != self.sort_by)
+            .nth(1)
+            .unwrap_or_default();
+        self.sort_by = next;
+        self.refresh_rows();
+    }
+
+    /// Steps to a longer refresh interval, or a shorter one if negative.
+    pub fn step_interval(&mut self, steps: isize) {
+        self.interval = step(&INTERVALS, self.interval, steps);
+        self.set_status(format!("Refreshing every {:?}", self.interval));
+    }
+
+    /// Steps to a longer history, or a shorter one if negative.
+    pub fn step_history_length(&mut self, steps: isize) {
+        self.history_length = step(&HISTORY_LENGTHS, self.history_length, steps);
+        self.set_status(format!(
+            "Keeping {} refreshes of history",
+            self.history_length
+        ));
+    }
+
+    pub fn toggle_total_chart(&mut self) {
+        self.show_total_chart = !self.show_total_chart;
+    }
+
+    pub fn cycle_chart_style(&mut self) {
+        self.chart_style = ChartStyle::iter()
+            .cycle()
+            .skip_while(|chart_style| *chart_style != self.chart_style)
+            .nth(1)
+            .unwrap_or_default();
+        let style = match self.chart_style {
+            ChartStyle::Bars => "bars",
+            ChartStyle::Braille => "braille",
+        };
+        self.set_status(format!("Charts drawn as {style}"));
+    }
+
+    pub fn cycle_chart_scale(&mut self) {
+        self.chart_scale = ChartScale::iter()
+            .cycle()
+            .skip_while(|chart_scale| *chart_scale != self.chart_scale)
+            .nth(1)
+            .unwrap_or_default();
+        let scale = match self.chart_scale {
+            ChartScale::Global => "the busiest row",
+            ChartScale::Row => "each row's own peak",
+        };
+        self.set_status(format!("Charts scaled to {scale}"));
+    }
+
+    pub fn toggle_log_scale(&mut self) {
+        self.log_scale = !self.log_scale;
+        let scale = if self.log_scale {
+            "logarithmic"
+        } else {
+            "linear"
+        };
+        self.set_status(format!("Charts on a {scale} scale"));
+    }
+
+    pub fn toggle_reverse(&mut self) {
+        self.reverse = !self.reverse;
+        self.refresh_rows();
+    }
+
+    fn sorted(&self, mut rows: Vec<ProcessRow>) -> Vec<ProcessRow> {
+        rows.sort_by(|a, b| compare_rows(a, b, self.sort_by, self.reverse));
+        rows
+    }
+
+    /// Rebuilds the table rows from the recorded history.
+    pub fn refresh_rows(&mut self) {
+        let snapshot = self.snapshot();
+        let process_rows = self.process_history.iter().map(|(proc_info, history)| {
+            let (current_bytes_downloaded, current_bytes_uploaded) = rates_at(
+                snapshot.map(|snapshot| &snapshot.processes),
+                proc_info,
+                history,
+            );
+            ProcessRow {
+                process: proc_info.clone(),
+                current_bytes_downloaded,
+                current_bytes_uploaded,
+                total_bytes_downloaded: history.total_bytes_downloaded,
+                total_bytes_uploaded: history.total_bytes_uploaded,
+                download_history: history.download_history.clone(),
+                upload_history: history.upload_history.clone(),
+                depth: 0,
+                children: 0,
+                collapsed: false,
+                cause: None,
+                connections: 0,
+                exited: self.show_exited && self.exited.contains(proc_info),
+                active_time: history.active_time,
+                alerting: self.alerting.contains(proc_info),
+                samples: history.samples,
+                download_stats: RateStats::default(),
+                upload_stats: RateStats::default(),
+            }
+        });
+
+        let mut rows = match self.group_by {
+            GroupBy::Process => {
+                // exited processes follow the live ones, in a section of their own
+                let (exited, live): (Vec<_>, Vec<_>) = process_rows.partition(|row| row.exited);
+                let mut rows = self.sorted(live);
+                rows.extend(self.sorted(exited));
+                rows
+            }
+            GroupBy::Tree => self.tree_rows(process_rows.collect()),
+            _ => {
+                let mut groups: HashMap<String, ProcessRow> = HashMap::new();
+                for row in process_rows {
+                    match groups.entry(self.group_label(&row.process)) {
+                        Entry::Occupied(mut group) => group.get_mut().merge(&row),
+                        Entry::Vacant(group) => {
+                            let process = ProcessInfo::new(group.key(), 0);
+                            group.insert(ProcessRow {
+                                process,
+                                exited: false,
+                                ..row
+                            });
+                        }
+                    }
+                }
+                self.sorted(groups.into_values().collect())
+            }
+        };
+
+        self.insert_unknown_breakdown(&mut rows);
+        if let Some(ref filter) = self.filter {
+            let owners = self
+                .connection_history
+                .iter()
+                .filter(|(connection, history)| {
+                    self.connection_matches(filter, connection, &history.process)
+                })
+                .map(|(_, history)| &history.process)
+                .collect::<HashSet<_>>();
+            let owner_groups = owners
+                .iter()
+                .map(|process| self.group_label(process))
+                .collect::<HashSet<_>>();
+            rows.retain(|row| {
+                process_matches(filter, &row.process)
+                    || owners.contains(&row.process)
+                    || (row.process.pid == 0 && owner_groups.contains(&row.process.name))
+            });
+        }
+        if rows.len() > self.max_rows {
+            rows.truncate(self.max_rows);
+        }
+        // computed once here rather than for every frame, over the merged histories
+        for row in rows.iter_mut() {
+            row.download_stats = RateStats::of(&row.download_history, row.samples);
+            row.upload_stats = RateStats::of(&row.upload_history, row.samples);
+        }
+        let mut connections_by_process: HashMap<&ProcessInfo, usize> = HashMap::new();
+        let mut connections_by_group: HashMap<String, usize> = HashMap::new();
+        for ConnectionHistory { process, .. } in self.connection_history.values() {
+            *connections_by_process.entry(process).or_default() += 1;
+            *connections_by_group
+                .entry(self.group_label(process))
+                .or_default() += 1;
+        }
+        for row in rows.iter_mut().filter(|row| row.cause.is_none()) {
+            row.connections = match row.process.pid {
+                0 => connections_by_group.get(&row.process.name),
+                _ => connections_by_process.get(&row.process),
+            }
+            .copied()
+            .unwrap_or_default();
+        }
+        self.process_rows = rows;
+        self.process_cursor
+            .follow(&self.process_rows, |row| row.process.clone());
+
+        let mut connection_rows = self
+            .connection_history
             .iter()
-            .map(|(proc_info, history)| {
-                let current = processes.get(proc_info).cloned().unwrap_or_default();
+            .map(
+                |(connection, ConnectionHistory { process, history })| ConnectionRow {
+                    connection: *connection,
+                    process: process.clone(),
+                    current_bytes_downloaded: history.current_bytes_downloaded,
+                    current_bytes_uploaded: history.current_bytes_uploaded,
+                    total_bytes_downloaded: history.total_bytes_downloaded,
+                    total_bytes_uploaded: history.total_bytes_uploaded,
+                    download_history: history.download_history.clone(),
+                    upload_history: history.upload_history.clone(),
+                    active_time: history.active_time,
+                    download_stats: RateStats::of(&history.download_history, history.samples),
+                    upload_stats: RateStats::of(&history.upload_history, history.samples),
+                },
+            )
+            .collect::<Vec<_>>();
+        if let Some(ref filter) = self.filter {
+            connection_rows
+                .retain(|row| self.connection_matches(filter, &row.connection, &row.process));
+        }
+        connection_rows.sort_by(|a, b| compare_rows(a, b, self.sort_by, self.reverse));
+        connection_rows.truncate(self.max_rows);
+        self.connection_rows = connection_rows;
+        self.connection_cursor
+            .follow(&self.connection_rows, |row| row.connection);
+
+        let mut processes_by_remote: HashMap<IpAddr, HashSet<&ProcessInfo>> = HashMap::new();
+        for (connection, ConnectionHistory { process, .. }) in &self.connection_history {
+            processes_by_remote
+                .entry(connection.remote_socket.ip)
+                .or_default()
+                .insert(process);
+        }
+        let mut remote_rows = self
+            .remote_history
+            .iter()
+            .map(|(ip, history)| RemoteRow {
+                ip: *ip,
+                processes: processes_by_remote.get(ip).map_or(0, HashSet::len),
+                current_bytes_downloaded: history.current_bytes_downloaded,
+                current_bytes_uploaded: history.current_bytes_uploaded,
+                total_bytes_downloaded: history.total_bytes_downloaded,
+                total_bytes_uploaded: history.total_bytes_uploaded,
+                download_history: history.download_history.clone(),
+                upload_history: history.upload_history.clone(),
+                active_time: history.active_time,
+                download_stats: RateStats::of(&history.download_history, history.samples),
+                upload_stats: RateStats::of(&history.upload_history, history.samples),
+            })
+            .collect::<Vec<_>>();
+        if let Some(ref filter) = self.filter {
+            remote_rows.retain(|row| {
+                self.host_matches(filter, row.ip)
+                    || processes_by_remote.get(&row.ip).is_some_and(|processes| {
+                        processes
+                            .iter()
+                            .any(|process| process_matches(filter, process))
+                    })
+            });
+        }
+        remote_rows.sort_by(|a, b| compare_rows(a, b, self.sort_by, self.reverse));
+        remote_rows.truncate(self.max_rows);
+        self.remote_rows = remote_rows;
+        self.remote_cursor.follow(&self.remote_rows, |row| row.ip);
+    }
+
+    /// Asks which signal to send to a process, or only for confirmation if the signal
+    /// is given.
+    pub fn prompt_signal(&mut self, target: ProcessInfo, signal: Option<Signal>) {
+        // group rows and <UNKNOWN> are not backed by a process
+        if target.pid == 0 {
+            self.set_status(format!("{} is not a single process", target.name));
+            return;
+        }
+        self.signal_prompt = Some(match signal {
+            Some(signal) => SignalPrompt::Confirm { target, signal },
+            None => SignalPrompt::Picker { target, cursor: 0 },
+        });
+    }
+
+    pub fn move_signal_cursor(&mut self, delta: isize) {
+        if let Some(SignalPrompt::Picker { ref mut cursor, .. }) = self.signal_prompt {
+            *cursor = cursor
+                .saturating_add_signed(delta)
+                .min(Signal::ALL.len() - 1);
+        }
+    }
+
+    /// Picks the signal under the cursor of the picker, if it is open.
+    pub fn choose_signal(&mut self) {
+        if let Some(SignalPrompt::Picker { ref target, cursor }) = self.signal_prompt {
+            self.signal_prompt = Some(SignalPrompt::Confirm {
+                target: target.clone(),
+                signal: Signal::ALL[cursor],
+            });
+        }
+    }
+
+    /// Sends the signal awaiting confirmation, if any.
+    pub fn confirm_signal(&mut self) {
+        let Some(SignalPrompt::Confirm { target, signal }) = self.signal_prompt.take() else {
+            return;
+        };
+        // the process may have exited while the prompt was open, and its PID been reused
+        let running = is_running(&target);
+        let ProcessInfo { name, pid, .. } = target;
+        if !running {
+            self.set_status(format!("Not sending {signal}: {name} ({pid}) has exited"));
+            return;
+        }
+        match send_signal(pid, signal) {
+            Ok(()) => self.set_status(format!("Sent {signal} to {name} ({pid})")),
+            Err(err) => self.set_status(format!("Cannot send {signal} to {name} ({pid}): {err}")),
+        }
+    }
+
+    pub fn cancel_signal(&mut self) {
+        self.signal_prompt = None;
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.help = match self.help {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    pub fn scroll_help(&mut self, delta: isize) {
+        if let Some(ref mut offset) = self.help {
+            let last = Action::ALL.len().saturating_sub(1);
+            *offset = offset.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    fn set_status(&mut self, status: String) {
+        self.status = Some((Instant::now(), status));
+    }
+
+    /// Returns the outcome of the last action, unless it is too old to be of interest.
+    pub fn status(&self) -> Option<&str> {
+        self.status
+            .as_ref()
+            .filter(|(time, _)| time.elapsed() < STATUS_TIMEOUT)
+            .map(|(_, status)| status.as_str())
+    }
+
+    /// Starts typing the filter.
+    pub fn start_filter(&mut self) {
+        self.editing_filter = true;
+    }
+
+    /// Adds a character to the filter and applies it.
+    pub fn push_filter_char(&mut self, ch: char) {
+        self.filter_text.push(ch);
+        self.apply_filter();
+    }
+
+    pub fn pop_filter_char(&mut self) {
+        self.filter_text.pop();
+        self.apply_filter();
+    }
+
+    /// Stops typing, keeping the filter.
+    pub fn confirm_filter(&mut self) {
+        self.editing_filter = false;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.editing_filter = false;
+        self.filter_text.clear();
+        self.apply_filter();
+    }
+
+    /// Compiles the filter text, which is matched literally while it is not (yet) a
+    /// valid regex.
+    fn apply_filter(&mut self) {
+        self.filter = match self.filter_text.as_str() {
+            "" => None,
+            text => RegexBuilder::new(text)
+                .case_insensitive(true)
+                .build()
+                .or_else(|_| {
+                    RegexBuilder::new(&regex::escape(text))
+                        .case_insensitive(true)
+                        .build()
+                })
+                .ok(),
+        };
+        self.refresh_rows();
+    }
+
+    /// Whether a remote address or its host name matches the filter.
+    fn host_matches(&self, filter: &Regex, ip: IpAddr) -> bool {
+        filter.is_match(&ip.to_string())
+            || self
+                .ip_to_host
+                .get(&ip)
+                .is_some_and(|host| filter.is_match(host))
+    }
+
+    /// Whether a connection, or the process owning it, matches the filter.
+    fn connection_matches(
+        &self,
+        filter: &Regex,
+        connection: &Connection,
+        process: &ProcessInfo,
+    ) -> bool {
+        process_matches(filter, process)
+            || self.host_matches(filter, connection.remote_socket.ip)
+            || filter.is_match(&connection.remote_socket.port.to_string())
+            || filter.is_match(&connection.local_socket.port.to_string())
+    }
+
+    /// Nests the breakdown of unattributed traffic under the `<UNKNOWN>` row.
+    fn insert_unknown_breakdown(&self, rows: &mut Vec<ProcessRow>) {
+        let unknown = unknown_process();
+        let Some(index) = rows.iter().position(|row| row.process == unknown) else {
+            return;
+        };
+
+        let parent = &mut rows[index];
+        parent.children = self.unknown_history.len();
+        parent.collapsed = self.collapsed.contains(&unknown);
+        if parent.collapsed {
+            return;
+        }
+        let depth = parent.depth + 1;
+
+        let snapshot = self.snapshot().map(|snapshot| &snapshot.unknown);
+        let breakdown = self
+            .unknown_history
+            .iter()
+            .map(|(traffic, UnknownHistory { cause, history })| {
+                let (current_bytes_downloaded, current_bytes_uploaded) =
+                    rates_at(snapshot, traffic, history);
                 ProcessRow {
-                    process: proc_info.clone(),
-                    current_bytes_downloaded: current.total_bytes_downloaded,
-                    current_bytes_uploaded: current.total_bytes_uploaded,
+                    process: ProcessInfo::new(&traffic.to_string(), 0),
+                    current_bytes_downloaded,
+                    current_bytes_uploaded,
                     total_bytes_downloaded: history.total_bytes_downloaded,
                     total_bytes_uploaded: history.total_bytes_uploaded,
                     download_history: history.download_history.clone(),
                     upload_history: history.upload_history.clone(),
+                    depth,
+                    children: 0,
+                    collapsed: false,
+                    cause: Some(cause.clone()),
+                    connections: 0,
+                    exited: false,
+                    active_time: history.active_time,
+                    alerting: false,
+                    samples: history.samples,
+                    download_stats: RateStats::default(),
+                    upload_stats: RateStats::default(),
                 }
             })
+            .collect();
+        rows.splice(index + 1..index + 1, self.sorted(breakdown));
+    }
+
+    /// Evaluates the alert rules against the traffic of a tick, and runs the hooks of
+    /// those that fire.
+    fn check_alerts(
+        &mut self,
+        processes: &HashMap<ProcessInfo, NetworkData>,
+        total: &NetworkData,
+        seconds: f64,
+    ) {
+        let at = Local::now();
+        self.fired_alerts.clear();
+        for alert in self.alerts.iter_mut() {
+            let data = match alert.rule.process {
+                Some(ref name) => processes
+                    .iter()
+                    .filter(|(proc_info, _)| proc_info.name == *name)
+                    .fold(NetworkData::default(), |sum, (_, data)| NetworkData {
+                        total_bytes_downloaded: sum.total_bytes_downloaded
+                            + data.total_bytes_downloaded,
+                        total_bytes_uploaded: sum.total_bytes_uploaded + data.total_bytes_uploaded,
+                    }),
+                None => total.clone(),
+            };
+            if let Some(event) = alert.evaluate(at, seconds, &data) {
+                warn!("Alert {event}");
+                alerts::dispatch(&alert.rule, &event);
+                self.fired_alerts.push(event);
+            }
+        }
+        // rules watch process names, but only the processes of that name that are
+        // sending or receiving are to blame
+        self.alerting = self
+            .alerts
+            .iter()
+            .filter(|alert| alert.firing)
+            .filter_map(|alert| alert.rule.process.as_ref())
+            .flat_map(|name| {
+                processes
+                    .keys()
+                    .filter(move |proc_info| proc_info.name == *name)
+            })
+            .cloned()
+            .collect();
+        if let Some(status) = self
+            .fired_alerts
+            .last()
+            .map(|event| format!("Alert {event}"))
+        {
+            self.set_status(status);
+        }
+    }
+
+    /// Adds the traffic of a tick to that of process names and units yet to be recorded.
+    fn tally_usage(
+        &mut self,
+        processes: &HashMap<ProcessInfo, NetworkData>,
+        process_meta: &HashMap<ProcessInfo, ProcessMeta>,
+    ) {
+        for (proc_info, data) in processes {
+            let unit = process_meta
+                .get(proc_info)
+                .and_then(|meta| meta.unit.clone());
+            let keys = [
+                Some((UsageKind::Process, proc_info.name.clone())),
+                unit.map(|unit| (UsageKind::Unit, unit)),
+            ];
+            for (kind, name) in keys.into_iter().flatten() {
+                let usage = self.usage.entry(UsageKey { kind, name }).or_default();
+                usage.total_bytes_downloaded += data.total_bytes_downloaded;
+                usage.total_bytes_uploaded += data.total_bytes_uploaded;
+            }
+        }
+    }
+
+    /// Takes the traffic tallied since the last call, to be recorded.
+    pub fn take_usage(&mut self) -> HashMap<UsageKey, NetworkData> {
+        mem::take(&mut self.usage)
+    }
+
+    /// Hands the history of an exited process over to a new one with the same executable,
+    /// so that a restarting service keeps a single history line.
+    fn merge_restarts(
+        &mut self,
+        processes: &HashMap<ProcessInfo, NetworkData>,
+        running: &HashSet<ProcessInfo>,
+    ) {
+        // without a list of running processes, nothing is known to have exited
+        if !self.merge_restarts || running.is_empty() {
+            return;
+        }
+        for proc_info in processes.keys() {
+            if proc_info.pid == 0 || self.process_history.contains_key(proc_info) {
+                continue;
+            }
+            let restarted = executable(&self.process_meta, proc_info);
+            // the latest one to have started is the one that was restarted
+            let Some(previous) = self
+                .process_history
+                .keys()
+                .filter(|earlier| {
+                    earlier.pid != 0
+                        && !running.contains(earlier)
+                        && executable(&self.process_meta, earlier) == restarted
+                })
+                .max_by_key(|earlier| (earlier.start_time, earlier.pid))
+                .cloned()
+            else {
+                continue;
+            };
+            if let Some(history) = self.process_history.remove(&previous) {
+                self.process_history.insert(proc_info.clone(), history);
+            }
+            for snapshot in self.snapshots.iter_mut() {
+                if let Some(rates) = snapshot.processes.remove(&previous) {
+                    snapshot.processes.insert(proc_info.clone(), rates);
+                }
+            }
+            for connection_history in self.connection_history.values_mut() {
+                if connection_history.process == previous {
+                    connection_history.process = proc_info.clone();
+                }
+            }
+            if self.collapsed.remove(&previous) {
+                self.collapsed.insert(proc_info.clone());
+            }
+            if self.detail.as_ref() == Some(&previous) {
+                self.detail = Some(proc_info.clone());
+            }
+            self.exited.remove(&previous);
+        }
+    }
+
+    /// Forgets processes that exited and have been idle for long enough, and the longest
+    /// idle ones beyond the cap.
+    fn evict_processes(&mut self, running: &HashSet<ProcessInfo>) {
+        // without a list of running processes, nothing is known to have exited
+        if !running.is_empty() {
+            self.exited = self
+                .process_history
+                .keys()
+                .filter(|proc_info| proc_info.pid != 0 && !running.contains(proc_info))
+                .cloned()
+                .collect();
+        }
+        // shown exited processes stay until their traffic leaves the charts
+        let keep_exited_for = match self.show_exited {
+            true => self.evict_after.max(self.history_length),
+            false => self.evict_after,
+        };
+        let exited = &self.exited;
+        self.process_history.retain(|proc_info, history| {
+            !exited.contains(proc_info) || history.idle_ticks < keep_exited_for
+        });
+
+        let excess = self
+            .process_history
+            .len()
+            .saturating_sub(self.max_processes);
+        if excess > 0 {
+            let mut by_idle_ticks = self
+                .process_history
+                .iter()
+                .map(|(proc_info, history)| (history.idle_ticks, proc_info.clone()))
+                .collect::<Vec<_>>();
+            by_idle_ticks.sort_unstable_by_key(|(idle_ticks, _)| Reverse(*idle_ticks));
+            for (_, proc_info) in by_idle_ticks.into_iter().take(excess) {
+                self.process_history.remove(&proc_info);
+            }
+        }
+        let process_history = &self.process_history;
+        self.exited
+            .retain(|proc_info| process_history.contains_key(proc_info));
+    }
+
+    /// The tick the table is looking at, unless it shows the latest one.
+    fn snapshot(&self) -> Option<&Snapshot> {
+        self.history_cursor
+            .and_then(|back| self.snapshots.iter().rev().nth(back))
+    }
+
+    /// When the tick the table is looking at happened, unless it shows the latest one.
+    pub fn history_cursor_time(&self) -> Option<DateTime<Local>> {
+        self.snapshot().map(|snapshot| snapshot.at)
+    }
+
+    /// Looks the given number of ticks further back, or forward if negative.
+    pub fn move_history_cursor(&mut self, delta: isize) {
+        let last = self.snapshots.len().saturating_sub(1);
+        let back = self
+            .history_cursor
+            .unwrap_or_default()
+            .saturating_add_signed(delta)
+            .min(last);
+        self.history_cursor = (back > 0).then_some(back);
+        self.refresh_rows();
+    }
+
+    /// Goes back to showing the latest tick.
+    pub fn clear_history_cursor(&mut self) {
+        if self.history_cursor.take().is_some() {
+            self.refresh_rows();
+        }
+    }
+
+    /// Returns the last known attributes of a process.
+    pub fn process_meta(&self, proc_info: &ProcessInfo) -> Option<&ProcessMeta> {
+        self.process_meta.get(proc_info)
+    }
+
+    /// Moves the cursor of a table to the given row, or to the closest one that exists.
+    fn select(&mut self, table: Table, index: usize) {
+        match table {
+            Table::Processes => self
+                .process_cursor
+                .select(index, &self.process_rows, |row| row.process.clone()),
+            Table::Connections => {
+                self.connection_cursor
+                    .select(index, &self.connection_rows, |row| row.connection)
+            }
+            Table::Remotes => self
+                .remote_cursor
+                .select(index, &self.remote_rows, |row| row.ip),
+        }
+    }
+
+    /// Moves the cursor of a table by the given number of rows, up if negative.
+    pub fn move_selection(&mut self, table: Table, delta: isize) {
+        let selected = match table {
+            Table::Processes => self.process_cursor.selected,
+            Table::Connections => self.connection_cursor.selected,
+            Table::Remotes => self.remote_cursor.selected,
+        };
+        self.select(table, selected.saturating_add_signed(delta));
+    }
+
+    /// Moves the cursor of a table by the given number of screenfuls, up if negative.
+    pub fn move_page(&mut self, table: Table, pages: isize) {
+        let page_size = self.page_size.max(1) as isize;
+        self.move_selection(table, pages.saturating_mul(page_size));
+    }
+
+    pub fn select_first(&mut self, table: Table) {
+        self.select(table, 0);
+    }
+
+    pub fn select_last(&mut self, table: Table) {
+        self.select(table, usize::MAX);
+    }
+
+    /// Scrolls a table so that its cursor is visible, given how many rows fit.
+    pub fn scroll_into_view(&mut self, table: Table, page_size: usize) {
+        self.page_size = page_size;
+        match table {
+            Table::Processes => self
+                .process_cursor
+                .scroll_into_view(self.process_rows.len(), page_size),
+            Table::Connections => self
+                .connection_cursor
+                .scroll_into_view(self.connection_rows.len(), page_size),
+            Table::Remotes => self
+                .remote_cursor
+                .scroll_into_view(self.remote_rows.len(), page_size),
+        }
+    }
+
+    /// The row under the cursor of the process table.
+    pub fn selected_process(&self) -> Option<&ProcessRow> {
+        self.process_rows.get(self.process_cursor.selected)
+    }
+
+    /// Shows the details of the selected row.
+    pub fn open_detail(&mut self) {
+        self.detail = self.selected_process().map(|row| row.process.clone());
+    }
+
+    pub fn close_detail(&mut self) {
+        self.detail = None;
+    }
+
+    /// Returns the connections of a row, which may be a group of processes.
+    pub fn row_connections(&self, process: &ProcessInfo) -> Vec<&ConnectionRow> {
+        self.connection_rows
+            .iter()
+            .filter(|row| {
+                row.process == *process
+                    || (process.pid == 0 && self.group_label(&row.process) == process.name)
+            })
+            .collect()
+    }
+
+    /// Collapses the subtree of the selected row, or expands it if it was collapsed.
+    pub fn toggle_collapse(&mut self) {
+        let Some(row) = self.process_rows.get(self.process_cursor.selected) else {
+            return;
+        };
+        if row.children == 0 {
+            return;
+        }
+        if !self.collapsed.remove(&row.process) {
+            self.collapsed.insert(row.process.clone());
+        }
+        self.refresh_rows();
+    }
+
+    /// Collapses every expanded row in tree mode, or expands all rows if some were collapsed.
+    pub fn toggle_collapse_all(&mut self) {
+        if self.collapsed.is_empty() {
+            self.collapsed = self
+                .process_rows
+                .iter()
+                .filter(|row| row.children > 0)
+                .map(|row| row.process.clone())
+                .collect();
+        } else {
+            self.collapsed.clear();
+        }
+        self.refresh_rows();
+    }
+
+    /// Nests process rows under their closest ancestor that has a row of its own.
+    ///
+    /// Each row shows the totals of its whole subtree.
+    fn tree_rows(&self, rows: Vec<ProcessRow>) -> Vec<ProcessRow> {
+        let mut by_pid: HashMap<u32, Vec<&ProcessInfo>> = HashMap::new();
+        for proc_info in self.process_meta.keys() {
+            by_pid.entry(proc_info.pid).or_default().push(proc_info);
+        }
+        // a reused PID is only the parent if it was started before the child
+        let parent = |proc_info: &ProcessInfo| {
+            let ppid = self.process_meta.get(proc_info)?.ppid?;
+            by_pid
+                .get(&ppid)?
+                .iter()
+                .max_by_key(|parent| (parent.start_time <= proc_info.start_time, parent.start_time))
+                .copied()
+        };
+        let index_of = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| (&row.process, index))
+            .collect::<HashMap<_, _>>();
+        let closest_ancestor = |proc_info: &ProcessInfo| {
+            let mut current = proc_info;
+            for _ in 0..MAX_TREE_DEPTH {
+                current = parent(current)?;
+                if current.pid == 0 {
+                    return None;
+                }
+                if let Some(&index) = index_of.get(current) {
+                    return Some(index);
+                }
+            }
+            None
+        };
+
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut roots = vec![];
+        for (index, row) in rows.iter().enumerate() {
+            match closest_ancestor(&row.process) {
+                Some(parent) if parent != index => children.entry(parent).or_default().push(index),
+                _ => roots.push(index),
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut subtrees = roots
+            .into_iter()
+            .map(|root| self.flatten_subtree(root, 0, &rows, &children, &mut visited))
             .collect::<Vec<_>>();
+        subtrees.sort_by(|a, b| compare_rows(&a[0], &b[0], self.sort_by, self.reverse));
+        subtrees.into_iter().flatten().collect()
+    }
 
-        rows.sort_by_key(|row| cmp::Reverse(row.total_bytes_downloaded));
-        if rows.len() > MAX_BANDWIDTH_ITEMS {
-            rows.truncate(MAX_BANDWIDTH_ITEMS);
+    /// Returns the visible rows of a subtree, starting with its root.
+    fn flatten_subtree(
+        &self,
+        index: usize,
+        depth: usize,
+        rows: &[ProcessRow],
+        children: &HashMap<usize, Vec<usize>>,
+        visited: &mut HashSet<usize>,
+    ) -> Vec<ProcessRow> {
+        visited.insert(index);
+        let mut subtrees = vec![];
+        for &child in children.get(&index).into_iter().flatten() {
+            if !visited.contains(&child) {
+                subtrees.push(self.flatten_subtree(child, depth + 1, rows, children, visited));
+            }
+        }
+        subtrees.sort_by(|a, b| compare_rows(&a[0], &b[0], self.sort_by, self.reverse));
+
+        let mut root = rows[index].clone();
+        for subtree in &subtrees {
+            root.merge(&subtree[0]);
+        }
+        root.depth = depth;
+        root.children = subtrees.len();
+        root.collapsed = self.collapsed.contains(&root.process);
+
+        let mut flattened = vec![];
+        let collapsed = root.collapsed;
+        flattened.push(root);
+        if !collapsed {
+            flattened.extend(subtrees.into_iter().flatten());
+        }
+        flattened
+    }
+
+    /// Returns the name of the group a process is aggregated into.
+    fn group_label(&self, proc_info: &ProcessInfo) -> String {
+        if proc_info.pid == 0 {
+            return proc_info.name.clone();
+        }
+        let meta = self.process_meta.get(proc_info);
+        let label = match self.group_by {
+            GroupBy::Process | GroupBy::Name | GroupBy::Tree => Some(proc_info.name.clone()),
+            GroupBy::Cgroup => meta.and_then(|meta| meta.cgroup.clone()),
+            GroupBy::Unit => meta.and_then(|meta| meta.unit.clone()),
+            GroupBy::Container => meta.and_then(|meta| meta.container.clone()),
+            GroupBy::Netns => {
+                meta.map(|meta| meta.netns.clone().unwrap_or_else(|| "<host>".to_string()))
+            }
+            GroupBy::User => meta.and_then(|meta| meta.uid).map(|uid| {
+                self.user_names
+                    .get(&uid)
+                    .cloned()
+                    .unwrap_or_else(|| uid.to_string())
+            }),
+        };
+        label.unwrap_or_else(|| "<none>".to_string())
+    }
+}
+
+/// Returns the process owning a socket: one of our own namespace first, then one of the
+/// others in a stable order.
+fn socket_owner<'a>(
+    local_socket: &LocalSocket,
+    sockets_to_procs: &'a HashMap<LocalSocket, ProcessInfo>,
+    netns_sockets: &'a BTreeMap<u64, HashMap<LocalSocket, ProcessInfo>>,
+) -> Option<&'a ProcessInfo> {
+    local_socket.find_in(sockets_to_procs).or_else(|| {
+        netns_sockets
+            .values()
+            .find_map(|sockets| local_socket.find_in(sockets))
+    })
+}
+
+/// Whether the name or the PID of a process matches the filter.
+fn process_matches(filter: &Regex, process: &ProcessInfo) -> bool {
+    filter.is_match(&process.name)
+        || (process.pid != 0 && filter.is_match(&process.pid.to_string()))
+}
+
+/// The executable of a process, or its name if the path is not known.
+fn executable<'a>(
+    process_meta: &'a HashMap<ProcessInfo, ProcessMeta>,
+    proc_info: &'a ProcessInfo,
+) -> &'a str {
+    process_meta
+        .get(proc_info)
+        .and_then(|meta| meta.exe.as_deref())
+        .unwrap_or(&proc_info.name)
+}
+
+/// The placeholder owning all traffic that cannot be attributed to a process.
+fn unknown_process() -> ProcessInfo {
+    ProcessInfo::new("<UNKNOWN>", 0)
+}
+
+/// Returns the value the given number of steps up or down from `current`, which need not
+/// be one of the steps itself.
+fn step<T: Copy + PartialOrd>(steps: &[T], current: T, delta: isize) -> T {
+    let index = match delta.signum() {
+        1 => steps.iter().filter(|step| **step <= current).count() + delta as usize - 1,
+        -1 => steps
+            .iter()
+            .filter(|step| **step < current)
+            .count()
+            .saturating_sub(delta.unsigned_abs()),
+        _ => return current,
+    };
+    steps[index.min(steps.len() - 1)]
+}
+
+/// Adds the bytes of a tick to the totals of a history, and their rate to its samples.
+fn push_sample(history: &mut ProcessHistory, data: &NetworkData, tick: Tick) {
+    ensure_full_history(history, tick.history_length);
+    let rate = data.per_second(tick.seconds);
+    history.current_bytes_downloaded = tick.smooth(
+        history.current_bytes_downloaded,
+        rate.total_bytes_downloaded,
+    );
+    history.current_bytes_uploaded =
+        tick.smooth(history.current_bytes_uploaded, rate.total_bytes_uploaded);
+    history.total_bytes_downloaded += data.total_bytes_downloaded;
+    history.total_bytes_uploaded += data.total_bytes_uploaded;
+    history.samples = history.samples.saturating_add(1);
+    match data.total_bytes_downloaded + data.total_bytes_uploaded {
+        0 => history.idle_ticks = history.idle_ticks.saturating_add(1),
+        _ => {
+            history.idle_ticks = 0;
+            history.active_time += Duration::from_secs_f64(tick.seconds);
+        }
+    }
+    history
+        .download_history
+        .push_back(data.total_bytes_downloaded as f64 / tick.seconds);
+    history
+        .upload_history
+        .push_back(data.total_bytes_uploaded as f64 / tick.seconds);
+    trim_history(history, tick.history_length);
+}
+
+/// Whether there was any traffic within the history window.
+fn is_active(history: &ProcessHistory) -> bool {
+    history.download_history.iter().any(|&value| value > 0.0)
+        || history.upload_history.iter().any(|&value| value > 0.0)
+}
+
+/// Starts a history with the totals an earlier one of the same traffic left off at.
+fn resume_history(totals: Option<NetworkData>) -> ProcessHistory {
+    let totals = totals.unwrap_or_default();
+    ProcessHistory {
+        total_bytes_downloaded: totals.total_bytes_downloaded,
+        total_bytes_uploaded: totals.total_bytes_uploaded,
+        ..Default::default()
+    }
+}
+
+/// Drops the histories that went idle, but keeps their totals aside so that they can be
+/// resumed. Beyond `MAX_IDLE_TOTALS`, the smallest totals are forgotten.
+fn retire_idle<K: Clone + Eq + Hash, V>(
+    histories: &mut HashMap<K, V>,
+    totals: &mut HashMap<K, NetworkData>,
+    history_of: impl Fn(&V) -> &ProcessHistory,
+) {
+    histories.retain(|key, value| {
+        let history = history_of(value);
+        if is_active(history) {
+            return true;
+        }
+        totals.insert(
+            key.clone(),
+            NetworkData {
+                total_bytes_downloaded: history.total_bytes_downloaded,
+                total_bytes_uploaded: history.total_bytes_uploaded,
+            },
+        );
+        false
+    });
+
+    let excess = totals.len().saturating_sub(MAX_IDLE_TOTALS);
+    if excess > 0 {
+        let mut by_size = totals
+            .iter()
+            .map(|(key, data)| {
+                let bytes = data.total_bytes_downloaded + data.total_bytes_uploaded;
+                (bytes, key.clone())
+            })
+            .collect::<Vec<_>>();
+        by_size.sort_unstable_by_key(|(bytes, _)| *bytes);
+        for (_, key) in by_size.into_iter().take(excess) {
+            totals.remove(&key);
         }
-        self.process_rows = rows;
     }
 }
 
-fn trim_history(history: &mut ProcessHistory) {
-    while history.download_history.len() > HISTORY_LENGTH {
+fn trim_history(history: &mut ProcessHistory, length: usize) {
+    while history.download_history.len() > length {
         history.download_history.pop_front();
     }
-    while history.upload_history.len() > HISTORY_LENGTH {
+    while history.upload_history.len() > length {
         history.upload_history.pop_front();
     }
 }
 
-fn ensure_full_history(history: &mut ProcessHistory) {
-    while history.download_history.len() < HISTORY_LENGTH {
+/// Returns the current rates of the histories that have any.
+fn current_rates<K: Clone + Eq + Hash, V>(
+    histories: &HashMap<K, V>,
+    history_of: impl Fn(&V) -> &ProcessHistory,
+) -> HashMap<K, NetworkData> {
+    histories
+        .iter()
+        .map(|(key, value)| (key, history_of(value)))
+        .filter(|(_, history)| {
+            history.current_bytes_downloaded > 0 || history.current_bytes_uploaded > 0
+        })
+        .map(|(key, history)| {
+            let rates = NetworkData {
+                total_bytes_downloaded: history.current_bytes_downloaded,
+                total_bytes_uploaded: history.current_bytes_uploaded,
+            };
+            (key.clone(), rates)
+        })
+        .collect()
+}
+
+/// Returns the rates of a history at the snapshot being looked at, or its latest ones.
+fn rates_at<K: Eq + Hash>(
+    snapshot: Option<&HashMap<K, NetworkData>>,
+    key: &K,
+    history: &ProcessHistory,
+) -> (u128, u128) {
+    match snapshot {
+        Some(snapshot) => snapshot.get(key).map_or((0, 0), |data| {
+            (data.total_bytes_downloaded, data.total_bytes_uploaded)
+        }),
+        None => (
+            history.current_bytes_downloaded,
+            history.current_bytes_uploaded,
+        ),
+    }
+}
+
+fn ensure_full_history(history: &mut ProcessHistory, length: usize) {
+    while history.download_history.len() < length {
         history.download_history.push_front(0.0);
     }
-    while history.upload_history.len() < HISTORY_LENGTH {
+    while history.upload_history.len() < length {
         history.upload_history.push_front(0.0);
     }
 }
 
-fn get_proc_info<'a>(
-    connections_to_procs: &'a HashMap<LocalSocket, ProcessInfo>,
-    local_socket: &LocalSocket,
-) -> Option<&'a ProcessInfo> {
-    connections_to_procs
-        // direct match
-        .get(local_socket)
-        // IPv4-mapped IPv6 addresses
-        .or_else(|| {
-            let swapped: IpAddr = match local_socket.ip {
-                IpAddr::V4(v4) => v4.to_ipv6_mapped().into(),
-                IpAddr::V6(v6) => v6.to_ipv4_mapped()?.into(),
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+    use crate::network::{ConnectionInfo, Protocol};
+
+    #[test]
+    fn step_between_values() {
+        let ms = Duration::from_millis;
+        assert_eq!(step(&INTERVALS, ms(1000), 1), ms(2000));
+        assert_eq!(step(&INTERVALS, ms(1000), -1), ms(500));
+        assert_eq!(step(&INTERVALS, ms(300), 1), ms(500));
+        assert_eq!(step(&INTERVALS, ms(300), -1), ms(250));
+        assert_eq!(step(&INTERVALS, ms(100), -1), ms(100));
+        assert_eq!(step(&HISTORY_LENGTHS, 3600, 1), 3600);
+        assert_eq!(step(&HISTORY_LENGTHS, 10, 1), 50);
+    }
+
+    #[test]
+    fn evict_exited_and_idle_processes() {
+        let idle = |idle_ticks| ProcessHistory {
+            idle_ticks,
+            ..Default::default()
+        };
+        let mut state = UIState {
+            evict_after: 5,
+            max_processes: 3,
+            ..Default::default()
+        };
+        state.process_history = HashMap::from([
+            (ProcessInfo::new("exited", 1), idle(5)),
+            (ProcessInfo::new("exiting", 2), idle(4)),
+            (ProcessInfo::new("sleeping", 3), idle(50)),
+            (ProcessInfo::new("busy", 4), idle(0)),
+            (ProcessInfo::new("quiet", 5), idle(20)),
+        ]);
+        state.evict_processes(&HashSet::from([
+            ProcessInfo::new("sleeping", 3),
+            ProcessInfo::new("busy", 4),
+            ProcessInfo::new("quiet", 5),
+        ]));
+
+        // the exited process is gone, then the longest idle one beyond the cap
+        let mut names = state
+            .process_history
+            .keys()
+            .map(|proc_info| proc_info.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, ["busy", "exiting", "quiet"]);
+        assert_eq!(
+            state.exited,
+            HashSet::from([ProcessInfo::new("exiting", 2)])
+        );
+    }
+
+    #[test]
+    fn merge_restarted_processes() {
+        let exited = ProcessInfo::new("nginx", 10).with_start_time(1);
+        let long_exited = ProcessInfo::new("nginx", 5).with_start_time(0);
+        let other = ProcessInfo::new("nginx", 11).with_start_time(1);
+        let restarted = ProcessInfo::new("nginx", 20).with_start_time(2);
+        // an unrelated process that was given the PID of the exited one
+        let reused = ProcessInfo::new("nginx", 10).with_start_time(3);
+        let history = |total_bytes_downloaded| ProcessHistory {
+            total_bytes_downloaded,
+            ..Default::default()
+        };
+        let mut state = UIState {
+            merge_restarts: true,
+            ..Default::default()
+        };
+        state.process_history = HashMap::from([
+            (exited.clone(), history(100)),
+            (long_exited.clone(), history(7)),
+            (other.clone(), history(5)),
+        ]);
+        let connection = Connection::new(
+            "1.1.1.1:443".parse().unwrap(),
+            Ipv4Addr::new(10, 0, 0, 2).into(),
+            40000,
+            Protocol::Tcp,
+        );
+        state.connection_history = HashMap::from([(
+            connection,
+            ConnectionHistory {
+                process: exited.clone(),
+                history: ProcessHistory::default(),
+            },
+        )]);
+        let processes = HashMap::from([(restarted.clone(), NetworkData::default())]);
+        let running = HashSet::from([other.clone(), restarted.clone(), reused]);
+        state.merge_restarts(&processes, &running);
+
+        // the running process of the same name keeps its own line
+        assert_eq!(
+            state.process_history[&restarted].total_bytes_downloaded,
+            100
+        );
+        assert_eq!(state.process_history[&other].total_bytes_downloaded, 5);
+        assert!(!state.process_history.contains_key(&exited));
+        // only the latest of the exited ones is taken over
+        assert_eq!(
+            state.process_history[&long_exited].total_bytes_downloaded,
+            7
+        );
+        assert_eq!(state.connection_history[&connection].process, restarted);
+    }
+
+    #[test]
+    fn merge_restart_of_process_without_sockets() {
+        let exited = ProcessInfo::new("nginx", 10).with_start_time(1);
+        let restarted = ProcessInfo::new("nginx", 20).with_start_time(2);
+        let connection = Connection::new(
+            "1.1.1.1:443".parse().unwrap(),
+            Ipv4Addr::new(10, 0, 0, 2).into(),
+            40000,
+            Protocol::Tcp,
+        );
+        let nginx = |exe: Option<&str>| ProcessMeta {
+            exe: exe.map(str::to_string),
+            ..Default::default()
+        };
+        let tick = |owner: Option<&ProcessInfo>, processes| {
+            let mut utilization = Utilization::new();
+            if owner.is_some() {
+                utilization.connections.insert(
+                    connection,
+                    ConnectionInfo {
+                        total_bytes_downloaded: 100,
+                        total_bytes_uploaded: 0,
+                    },
+                );
+            }
+            let open_sockets = OpenSockets {
+                sockets_to_procs: owner
+                    .map(|owner| HashMap::from([(connection.local_socket, owner.clone())]))
+                    .unwrap_or_default(),
+                netns_sockets: Default::default(),
+                orphan_sockets: HashMap::new(),
+                processes: HashMap::from(processes),
             };
-            connections_to_procs.get(&LocalSocket {
-                ip: swapped,
-                ..*local_socket
-            })
-        })
-        // address unspecified
-        .or_else(|| {
-            connections_to_procs.get(&LocalSocket {
-                ip: Ipv4Addr::UNSPECIFIED.into(),
-                ..*local_socket
-            })
-        })
-        .or_else(|| {
-            connections_to_procs.get(&LocalSocket {
-                ip: Ipv6Addr::UNSPECIFIED.into(),
-                ..*local_socket
+            (open_sockets, utilization)
+        };
+        let mut state = UIState {
+            merge_restarts: true,
+            interval: Duration::from_secs(1),
+            history_length: 10,
+            max_processes: 100,
+            evict_after: 100,
+            ..Default::default()
+        };
+
+        let (open_sockets, utilization) = tick(
+            Some(&exited),
+            [(exited.clone(), nginx(Some("/usr/sbin/nginx")))],
+        );
+        state.update(open_sockets, utilization);
+        // the sockets are closed, and the executable can no longer be read
+        let (open_sockets, utilization) = tick(None, [(exited.clone(), nginx(None))]);
+        state.update(open_sockets, utilization);
+        let (open_sockets, utilization) = tick(
+            Some(&restarted),
+            [(restarted.clone(), nginx(Some("/usr/sbin/nginx")))],
+        );
+        state.update(open_sockets, utilization);
+
+        assert!(!state.process_history.contains_key(&exited));
+        assert_eq!(
+            state.process_history[&restarted].total_bytes_downloaded,
+            200
+        );
+    }
+
+    #[test]
+    fn nest_processes_in_tree() {
+        let process = |name, pid, ppid| {
+            (
+                ProcessInfo::new(name, pid),
+                ProcessMeta {
+                    ppid: Some(ppid),
+                    ..Default::default()
+                },
+            )
+        };
+        let mut state = UIState {
+            group_by: GroupBy::Tree,
+            max_rows: 100,
+            ..Default::default()
+        };
+        // `sh` has no traffic of its own, and 41 and 42 are each other's parent
+        state.process_meta = HashMap::from([
+            process("init", 1, 0),
+            process("sshd", 10, 1),
+            process("sh", 15, 10),
+            process("bash", 20, 15),
+            process("looped", 40, 41),
+            process("a", 41, 42),
+            process("b", 42, 41),
+        ]);
+        state.process_history = ["init", "sshd", "bash", "looped"]
+            .into_iter()
+            .map(|name| {
+                let (proc_info, _) = state
+                    .process_meta
+                    .iter()
+                    .find(|(proc_info, _)| proc_info.name == name)
+                    .unwrap();
+                let history = ProcessHistory {
+                    total_bytes_downloaded: 10,
+                    ..Default::default()
+                };
+                (proc_info.clone(), history)
             })
-        })
+            .collect();
+        state.refresh_rows();
+
+        let rows = |state: &UIState| {
+            state
+                .process_rows
+                .iter()
+                .map(|row| {
+                    (
+                        row.process.name.clone(),
+                        row.depth,
+                        row.total_bytes_downloaded,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut expected = vec![
+            ("init".to_string(), 0, 30),
+            ("sshd".to_string(), 1, 20),
+            ("bash".to_string(), 2, 10),
+            ("looped".to_string(), 0, 10),
+        ];
+        let mut actual = rows(&state);
+        actual.sort_by_key(|(name, ..)| name != "looped");
+        expected.sort_by_key(|(name, ..)| name != "looped");
+        assert_eq!(actual, expected);
+
+        // collapsing a row hides its subtree but keeps its totals
+        state.select(
+            Table::Processes,
+            state
+                .process_rows
+                .iter()
+                .position(|row| row.process.name == "sshd")
+                .unwrap(),
+        );
+        state.toggle_collapse();
+        let collapsed = rows(&state);
+        assert!(collapsed.iter().all(|(name, ..)| name != "bash"));
+        assert!(collapsed.contains(&("sshd".to_string(), 1, 20)));
+    }
+
+    #[test]
+    fn follow_and_scroll_cursor() {
+        let mut cursor = TableCursor::default();
+        let rows = (0..10).collect::<Vec<u32>>();
+        cursor.select(usize::MAX, &rows, |&row| row);
+        assert_eq!(cursor.selected, 9);
+        cursor.scroll_into_view(rows.len(), 4);
+        assert_eq!(cursor.offset, 6);
+
+        // the cursor stays on its row when the order changes, and within the rows
+        let reversed = rows.iter().rev().copied().collect::<Vec<_>>();
+        cursor.follow(&reversed, |&row| row);
+        assert_eq!(cursor.selected, 0);
+        cursor.scroll_into_view(reversed.len(), 4);
+        assert_eq!(cursor.offset, 0);
+        cursor.select(5, &rows[..3], |&row| row);
+        assert_eq!(cursor.selected, 2);
+    }
+
+    #[test]
+    fn resume_totals_of_idle_remotes() {
+        let ip: IpAddr = Ipv4Addr::new(10, 0, 0, 1).into();
+        let data = NetworkData {
+            total_bytes_downloaded: 100,
+            total_bytes_uploaded: 10,
+        };
+        let tick = Tick::new(Duration::from_secs(1), None, 2);
+        let mut histories = HashMap::from([(ip, ProcessHistory::default())]);
+        let mut totals = HashMap::new();
+        push_sample(histories.get_mut(&ip).unwrap(), &data, tick);
+        for _ in 0..2 {
+            push_sample(
+                histories.get_mut(&ip).unwrap(),
+                &NetworkData::default(),
+                tick,
+            );
+        }
+        retire_idle(&mut histories, &mut totals, |history| history);
+        assert!(histories.is_empty());
+
+        let mut history = resume_history(totals.remove(&ip));
+        push_sample(&mut history, &data, tick);
+        assert_eq!(history.total_bytes_downloaded, 200);
+        assert_eq!(history.total_bytes_uploaded, 20);
+    }
+
+    #[test]
+    fn look_back_at_earlier_ticks() {
+        let curl = ProcessInfo::new("curl", 1);
+        let local_ip: IpAddr = Ipv4Addr::new(10, 0, 0, 2).into();
+        let connection = Connection::new(
+            "1.1.1.1:443".parse().unwrap(),
+            local_ip,
+            40000,
+            Protocol::Tcp,
+        );
+        let mut state = UIState {
+            history_length: 10,
+            max_rows: 100,
+            max_processes: 100,
+            evict_after: 100,
+            smoothing: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        for downloaded in [1000, 3000, 0] {
+            let open_sockets = OpenSockets {
+                sockets_to_procs: HashMap::from([(connection.local_socket, curl.clone())]),
+                netns_sockets: Default::default(),
+                orphan_sockets: HashMap::new(),
+                processes: HashMap::from([(curl.clone(), ProcessMeta::default())]),
+            };
+            let mut utilization = Utilization::new();
+            utilization.connections.insert(
+                connection,
+                ConnectionInfo {
+                    total_bytes_downloaded: downloaded,
+                    total_bytes_uploaded: 0,
+                },
+            );
+            utilization.elapsed = Duration::from_secs(1);
+            state.update(open_sockets, utilization);
+        }
+        let shown = |state: &UIState| {
+            let row = &state.process_rows[0];
+            assert_eq!(row.process, curl);
+            row.current_bytes_downloaded
+        };
+        let latest = shown(&state);
+
+        // each tick shows the smoothed rate it showed at the time
+        state.move_history_cursor(1);
+        let previous = shown(&state);
+        assert_eq!(previous, 2129);
+        state.move_history_cursor(1);
+        assert_eq!(shown(&state), 632);
+        state.move_history_cursor(-1);
+        assert_eq!(shown(&state), previous);
+        state.clear_history_cursor();
+        assert_eq!(shown(&state), latest);
+        assert!(latest > 0 && latest < previous);
+    }
+
+    #[test]
+    fn account_without_updating() {
+        let curl = ProcessInfo::new("curl", 1);
+        let idle_curl = ProcessInfo::new("curl", 2);
+        let connection = Connection::new(
+            "1.1.1.1:443".parse().unwrap(),
+            Ipv4Addr::new(10, 0, 0, 2).into(),
+            40000,
+            Protocol::Tcp,
+        );
+        let open_sockets = OpenSockets {
+            sockets_to_procs: HashMap::from([(connection.local_socket, curl.clone())]),
+            netns_sockets: Default::default(),
+            orphan_sockets: HashMap::new(),
+            processes: HashMap::from([
+                (
+                    curl.clone(),
+                    ProcessMeta {
+                        unit: Some("backup.service".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                (idle_curl, ProcessMeta::default()),
+            ]),
+        };
+        let mut utilization = Utilization::new();
+        utilization.connections.insert(
+            connection,
+            ConnectionInfo {
+                total_bytes_downloaded: 100,
+                total_bytes_uploaded: 1,
+            },
+        );
+        let rule = toml::from_str("name = 'curl'\nprocess = 'curl'\nrate = '50'").unwrap();
+        let mut state = UIState {
+            record_usage: true,
+            interval: Duration::from_secs(1),
+            alerts: vec![Alert::new(rule)],
+            ..Default::default()
+        };
+        // as when paused, the traffic is accounted but not shown
+        state.account(&open_sockets, &utilization);
+        assert_eq!(state.fired_alerts.len(), 1);
+        // only the process of that name with traffic is to blame
+        assert_eq!(state.alerting, HashSet::from([curl]));
+        state.account(&open_sockets, &utilization);
+        assert!(state.process_history.is_empty());
+
+        let usage = state.take_usage();
+        let unit = UsageKey {
+            kind: UsageKind::Unit,
+            name: "backup.service".to_string(),
+        };
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[&unit].total_bytes_downloaded, 200);
+        assert!(state.take_usage().is_empty());
+    }
+
+    #[test]
+    fn rate_statistics() {
+        let history = (1..=20).map(f64::from).collect::<VecDeque<_>>();
+        let stats = RateStats::of(&history, 20);
+        assert_eq!(stats.peak, 20.0);
+        assert_eq!(stats.mean, 10.5);
+        assert_eq!(stats.p95, 19.0);
+        assert_eq!(RateStats::of(&VecDeque::new(), 5), RateStats::default());
+
+        // the padding from before the process was seen is left out
+        let padded = [0.0, 0.0, 4.0, 2.0].into_iter().collect::<VecDeque<_>>();
+        assert_eq!(RateStats::of(&padded, 2).mean, 3.0);
+        assert_eq!(RateStats::of(&padded, 100).mean, 1.5);
+    }
+
+    #[test]
+    fn rates_over_elapsed_time() {
+        let data = NetworkData {
+            total_bytes_downloaded: 1000,
+            total_bytes_uploaded: 0,
+        };
+        let mut history = ProcessHistory::default();
+        push_sample(
+            &mut history,
+            &data,
+            Tick::new(Duration::from_millis(250), None, 4),
+        );
+        assert_eq!(history.current_bytes_downloaded, 4000);
+        assert_eq!(history.total_bytes_downloaded, 1000);
+        assert_eq!(history.download_history, [0.0, 0.0, 0.0, 4000.0]);
+        assert_eq!(history.active_time, Duration::from_millis(250));
+
+        // a tick as long as the time constant moves the average ~63% of the way
+        let smoothing = Some(Duration::from_secs(2));
+        let mut history = ProcessHistory::default();
+        push_sample(
+            &mut history,
+            &data,
+            Tick::new(Duration::from_secs(2), smoothing, 4),
+        );
+        assert_eq!(history.current_bytes_downloaded, 316);
+        assert_eq!(history.download_history.back(), Some(&500.0));
+    }
 }
@@ -0,0 +1,211 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    net::IpAddr,
+};
+
+use crate::{
+    network::{Connection, LocalSocket, Protocol},
+    os::{OrphanSocket, ProcessInfo},
+};
+
+/// Well-known ports of services implemented in the kernel, for platforms where the
+/// kernel's own sockets cannot be listed.
+const KERNEL_PORTS: [(Protocol, u16); 3] = [
+    // NFS
+    (Protocol::Tcp, 2049),
+    (Protocol::Udp, 2049),
+    // WireGuard
+    (Protocol::Udp, 51820),
+];
+
+/// A slice of traffic that could not be attributed to any process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnknownTraffic {
+    pub protocol: Protocol,
+    pub local_port: u16,
+    pub remote_ip: IpAddr,
+}
+
+impl From<&Connection> for UnknownTraffic {
+    fn from(connection: &Connection) -> Self {
+        UnknownTraffic {
+            protocol: connection.local_socket.protocol,
+            local_port: connection.local_socket.port,
+            remote_ip: connection.remote_socket.ip,
+        }
+    }
+}
+
+impl fmt::Display for UnknownTraffic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let UnknownTraffic {
+            protocol,
+            local_port,
+            remote_ip,
+        } = self;
+        write!(f, "{protocol}/{local_port} ⇄ {remote_ip}")
+    }
+}
+
+/// The likely reason why traffic could not be attributed to a process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnknownCause {
+    /// The local end is not an address of this host, so the traffic is being forwarded.
+    Forwarded,
+    /// The socket is held by the kernel, e.g. for NFS or WireGuard.
+    Kernel,
+    /// The socket lives in another network namespace.
+    Netns(String),
+    /// The owning process exited since the last scan.
+    Exited(String),
+    /// The owning process closed the socket since the last scan.
+    Closed(String),
+    /// A process owns a socket on the same port, but bound to another local address.
+    Lookalike(String),
+    /// No idea.
+    Unresolved,
+}
+
+impl fmt::Display for UnknownCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownCause::Forwarded => write!(f, "forwarded"),
+            UnknownCause::Kernel => write!(f, "kernel"),
+            UnknownCause::Netns(netns) => write!(f, "netns {netns}"),
+            UnknownCause::Exited(name) => write!(f, "{name} exited"),
+            UnknownCause::Closed(name) => write!(f, "closed by {name}"),
+            UnknownCause::Lookalike(name) => write!(f, "{name} on other IP"),
+            UnknownCause::Unresolved => write!(f, "unresolved"),
+        }
+    }
+}
+
+/// What is known about sockets at the time of an update, used to guess causes.
+pub struct OrphanContext<'a> {
+    /// Addresses of the monitored interfaces.
+    pub local_ips: &'a HashSet<IpAddr>,
+    pub orphan_sockets: &'a HashMap<LocalSocket, OrphanSocket>,
+    /// Socket owners as of the previous update.
+    pub previous_sockets: &'a HashMap<LocalSocket, ProcessInfo>,
+    /// Running processes. Empty if the platform does not report them.
+    pub running: &'a HashSet<ProcessInfo>,
+    /// Socket owners by port and protocol, ignoring the local address.
+    pub lookalikes: &'a HashMap<(u16, Protocol), &'a ProcessInfo>,
+}
+
+impl UnknownCause {
+    pub fn classify(connection: &Connection, context: &OrphanContext) -> Self {
+        let local_socket = connection.local_socket;
+
+        if !context.local_ips.is_empty() && !context.local_ips.contains(&local_socket.ip) {
+            return UnknownCause::Forwarded;
+        }
+        match local_socket.find_in(context.orphan_sockets) {
+            Some(OrphanSocket::Kernel) => return UnknownCause::Kernel,
+            Some(OrphanSocket::Netns(netns)) => return UnknownCause::Netns(netns.clone()),
+            None => (),
+        }
+        if let Some(proc_info) = local_socket.find_in(context.previous_sockets) {
+            let name = proc_info.name.clone();
+            return match context.running.contains(proc_info) || context.running.is_empty() {
+                true => UnknownCause::Closed(name),
+                false => UnknownCause::Exited(name),
+            };
+        }
+        if let Some(proc_info) = context
+            .lookalikes
+            .get(&(local_socket.port, local_socket.protocol))
+        {
+            return UnknownCause::Lookalike(proc_info.name.clone());
+        }
+        if KERNEL_PORTS.iter().any(|&(protocol, port)| {
+            protocol == local_socket.protocol
+                && (port == local_socket.port || port == connection.remote_socket.port)
+        }) {
+            return UnknownCause::Kernel;
+        }
+        UnknownCause::Unresolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr};
+
+    use super::*;
+
+    const LOCAL_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    fn connection(local_ip: IpAddr, local_port: u16, remote_port: u16) -> Connection {
+        let remote = SocketAddr::new(Ipv4Addr::new(1, 2, 3, 4).into(), remote_port);
+        Connection::new(remote, local_ip, local_port, Protocol::Tcp)
+    }
+
+    fn classify(
+        connection: &Connection,
+        orphan_sockets: &HashMap<LocalSocket, OrphanSocket>,
+        previous_sockets: &HashMap<LocalSocket, ProcessInfo>,
+        lookalikes: &HashMap<(u16, Protocol), &ProcessInfo>,
+    ) -> UnknownCause {
+        let context = OrphanContext {
+            local_ips: &HashSet::from([LOCAL_IP]),
+            orphan_sockets,
+            previous_sockets,
+            running: &HashSet::from([ProcessInfo::new("curl", 1).with_start_time(100)]),
+            lookalikes,
+        };
+        UnknownCause::classify(connection, &context)
+    }
+
+    #[test]
+    fn forwarded_and_kernel_traffic() {
+        let foreign = connection(Ipv4Addr::new(10, 0, 0, 99).into(), 40000, 443);
+        assert_eq!(
+            classify(&foreign, &HashMap::new(), &HashMap::new(), &HashMap::new()),
+            UnknownCause::Forwarded
+        );
+
+        let nfs = connection(LOCAL_IP, 800, 2049);
+        assert_eq!(
+            classify(&nfs, &HashMap::new(), &HashMap::new(), &HashMap::new()),
+            UnknownCause::Kernel
+        );
+        let orphans = HashMap::from([(nfs.local_socket, OrphanSocket::Netns("blue".into()))]);
+        assert_eq!(
+            classify(&nfs, &orphans, &HashMap::new(), &HashMap::new()),
+            UnknownCause::Netns("blue".into())
+        );
+    }
+
+    #[test]
+    fn vanished_owners() {
+        let conn = connection(LOCAL_IP, 40000, 443);
+        let running = ProcessInfo::new("curl", 1).with_start_time(100);
+        let exited = ProcessInfo::new("wget", 2);
+        // an earlier process that was given the same PID
+        let reused = ProcessInfo::new("curl", 1).with_start_time(50);
+
+        let previous = HashMap::from([(conn.local_socket, running.clone())]);
+        assert_eq!(
+            classify(&conn, &HashMap::new(), &previous, &HashMap::new()),
+            UnknownCause::Closed("curl".into())
+        );
+        let previous = HashMap::from([(conn.local_socket, exited)]);
+        assert_eq!(
+            classify(&conn, &HashMap::new(), &previous, &HashMap::new()),
+            UnknownCause::Exited("wget".into())
+        );
+        let previous = HashMap::from([(conn.local_socket, reused)]);
+        assert_eq!(
+            classify(&conn, &HashMap::new(), &previous, &HashMap::new()),
+            UnknownCause::Exited("curl".into())
+        );
+
+        let lookalikes = HashMap::from([((40000, Protocol::Tcp), &running)]);
+        assert_eq!(
+            classify(&conn, &HashMap::new(), &HashMap::new(), &lookalikes),
+            UnknownCause::Lookalike("curl".into())
+        );
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::config::KeysConfig;
+
+/// Something a key can be bound to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Help,
+    Quit,
+    Pause,
+    Earlier,
+    Later,
+    NextTable,
+    TotalChart,
+    ChartStyle,
+    ChartScale,
+    LogScale,
+    SlowerRefresh,
+    FasterRefresh,
+    LongerHistory,
+    ShorterHistory,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Open,
+    Back,
+    Group,
+    Collapse,
+    CollapseAll,
+    Sort,
+    Reverse,
+    Filter,
+    Terminate,
+    Kill,
+    Signal,
+}
+
+impl Action {
+    /// Every action, in the order they are listed in the help overlay.
+    pub const ALL: [Action; 31] = [
+        Action::Help,
+        Action::Quit,
+        Action::Pause,
+        Action::Earlier,
+        Action::Later,
+        Action::NextTable,
+        Action::TotalChart,
+        Action::ChartStyle,
+        Action::ChartScale,
+        Action::LogScale,
+        Action::SlowerRefresh,
+        Action::FasterRefresh,
+        Action::LongerHistory,
+        Action::ShorterHistory,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Open,
+        Action::Back,
+        Action::Group,
+        Action::Collapse,
+        Action::CollapseAll,
+        Action::Sort,
+        Action::Reverse,
+        Action::Filter,
+        Action::Terminate,
+        Action::Kill,
+        Action::Signal,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Help => "Show or hide this help",
+            Action::Quit => "Quit",
+            Action::Pause => "Pause or resume the display",
+            Action::Earlier => "Look at an earlier moment while paused",
+            Action::Later => "Look at a later moment while paused",
+            Action::NextTable => "Switch between processes, connections and remote addresses",
+            Action::TotalChart => "Show or hide the total bandwidth chart",
+            Action::ChartStyle => "Switch between bar and braille charts",
+            Action::ChartScale => "Scale charts against the busiest row or each row's own peak",
+            Action::LogScale => "Switch between linear and logarithmic charts",
+            Action::SlowerRefresh => "Refresh less often",
+            Action::FasterRefresh => "Refresh more often",
+            Action::LongerHistory => "Keep a longer history",
+            Action::ShorterHistory => "Keep a shorter history",
+            Action::Up => "Move the cursor up",
+            Action::Down => "Move the cursor down",
+            Action::PageUp => "Move the cursor up a page",
+            Action::PageDown => "Move the cursor down a page",
+            Action::First => "Move the cursor to the first row",
+            Action::Last => "Move the cursor to the last row",
+            Action::Open => "Show details of the selected process",
+            Action::Back => "Close the details, or clear the filter",
+            Action::Group => "Change how traffic is grouped",
+            Action::Collapse => "Collapse or expand the subtree of the selected row",
+            Action::CollapseAll => "Collapse or expand all subtrees",
+            Action::Sort => "Change the sort column",
+            Action::Reverse => "Reverse the sort order",
+            Action::Filter => "Filter rows by a regex",
+            Action::Terminate => "Send SIGTERM to the selected process",
+            Action::Kill => "Send SIGKILL to the selected process",
+            Action::Signal => "Pick a signal to send to the selected process",
+        }
+    }
+}
+
+/// A key, as written in the config file: a single character (`q`, `X`, `/`), a
+/// named key (`space`, `tab`, `enter`, `esc`, `backspace`, `up`, `down`, `left`,
+/// `right`, `pageup`, `pagedown`, `home`, `end`, `f1`-`f12`), optionally prefixed
+/// with `ctrl-` or `alt-`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    const fn new(code: KeyCode) -> Self {
+        KeyBinding {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    const fn char(ch: char) -> Self {
+        KeyBinding::new(KeyCode::Char(ch))
+    }
+
+    const fn ctrl(ch: char) -> Self {
+        KeyBinding {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // the case of a character already tells whether shift was held
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers - KeyModifiers::SHIFT,
+            _ => key.modifiers,
+        };
+        self.code == key.code && self.modifiers == modifiers
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(key: String) -> Result<Self, Self::Error> {
+        key.parse()
+    }
+}
+
+impl std::str::FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = key;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if rest.len() > 1 && lower.starts_with("ctrl-") {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if rest.len() > 1 && lower.starts_with("alt-") {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key: {key}")),
+                },
+            },
+        };
+        Ok(KeyBinding { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(ch) => write!(f, "{ch}"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// A set of default bindings the config file can start from.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    /// `h`/`l` to leave and open details, `g`/`G` to jump, `Ctrl-f`/`Ctrl-b` to page
+    Vim,
+    /// function keys as in htop
+    Htop,
+}
+
+/// Which keys trigger which action.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(KeymapPreset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn new(preset: KeymapPreset) -> Self {
+        use KeyCode as K;
+        let key = KeyBinding::new;
+        let ch = KeyBinding::char;
+        let ctrl = KeyBinding::ctrl;
+
+        let bindings = Action::ALL
+            .iter()
+            .map(|&action| {
+                let keys = match (preset, action) {
+                    (KeymapPreset::Htop, Action::Help) => vec![key(K::F(1)), ch('?'), ch('h')],
+                    (_, Action::Help) => vec![ch('?')],
+                    (KeymapPreset::Htop, Action::Quit) => vec![key(K::F(10)), ch('q')],
+                    (_, Action::Quit) => vec![ch('q')],
+                    (KeymapPreset::Htop, Action::Pause) => vec![ch('Z'), ch(' ')],
+                    (_, Action::Pause) => vec![ch(' ')],
+                    (_, Action::Earlier) => vec![key(K::Left), ch('[')],
+                    (_, Action::Later) => vec![key(K::Right), ch(']')],
+                    (_, Action::NextTable) => vec![key(K::Tab)],
+                    (_, Action::TotalChart) => vec![ch('t')],
+                    (_, Action::ChartStyle) => vec![ch('b')],
+                    (_, Action::ChartScale) => vec![ch('a')],
+                    (_, Action::LogScale) => vec![ch('L')],
+                    (_, Action::SlowerRefresh) => vec![ch('+'), ch('=')],
+                    (_, Action::FasterRefresh) => vec![ch('-')],
+                    (_, Action::LongerHistory) => vec![ch('>')],
+                    (_, Action::ShorterHistory) => vec![ch('<')],
+                    (KeymapPreset::Htop, Action::Up) => vec![key(K::Up)],
+                    (_, Action::Up) => vec![key(K::Up), ch('k')],
+                    (KeymapPreset::Htop, Action::Down) => vec![key(K::Down)],
+                    (_, Action::Down) => vec![key(K::Down), ch('j')],
+                    (KeymapPreset::Vim, Action::PageUp) => {
+                        vec![key(K::PageUp), ctrl('b'), ctrl('u')]
+                    }
+                    (_, Action::PageUp) => vec![key(K::PageUp)],
+                    (KeymapPreset::Vim, Action::PageDown) => {
+                        vec![key(K::PageDown), ctrl('f'), ctrl('d')]
+                    }
+                    (_, Action::PageDown) => vec![key(K::PageDown)],
+                    (KeymapPreset::Vim, Action::First) => vec![key(K::Home), ch('g')],
+                    (_, Action::First) => vec![key(K::Home)],
+                    (KeymapPreset::Vim, Action::Last) => vec![key(K::End), ch('G')],
+                    (_, Action::Last) => vec![key(K::End)],
+                    (KeymapPreset::Vim, Action::Open) => vec![key(K::Enter), ch('l')],
+                    (_, Action::Open) => vec![key(K::Enter)],
+                    (KeymapPreset::Vim, Action::Back) => vec![key(K::Esc), ch('h')],
+                    (_, Action::Back) => vec![key(K::Esc)],
+                    (KeymapPreset::Vim, Action::Group) => vec![ch('o')],
+                    (_, Action::Group) => vec![ch('g')],
+                    (_, Action::Collapse) => vec![ch('c')],
+                    (KeymapPreset::Htop, Action::CollapseAll) => vec![key(K::F(5)), ch('C')],
+                    (_, Action::CollapseAll) => vec![ch('C')],
+                    (KeymapPreset::Htop, Action::Sort) => vec![key(K::F(6)), ch('s')],
+                    (_, Action::Sort) => vec![ch('s')],
+                    (KeymapPreset::Htop, Action::Reverse) => vec![ch('I'), ch('r')],
+                    (_, Action::Reverse) => vec![ch('r')],
+                    (KeymapPreset::Htop, Action::Filter) => vec![key(K::F(4)), ch('/')],
+                    (_, Action::Filter) => vec![ch('/')],
+                    (_, Action::Terminate) => vec![ch('x')],
+                    (_, Action::Kill) => vec![ch('X')],
+                    (KeymapPreset::Htop, Action::Signal) => vec![key(K::F(9)), ch('k')],
+                    (_, Action::Signal) => vec![key(K::F(9))],
+                };
+                (action, keys)
+            })
+            .collect();
+        Keymap { bindings }
+    }
+
+    /// Starts from the preset and rebinds the actions given in the config file. A key
+    /// rebound to one action is taken away from any other.
+    pub fn from_config(config: &KeysConfig) -> Self {
+        let mut keymap = Keymap::new(config.preset.unwrap_or_default());
+        for (action, keys) in &config.bind {
+            for (_, bound) in &mut keymap.bindings {
+                bound.retain(|key| !keys.contains(key));
+            }
+            if let Some((_, bound)) = keymap.bindings.iter_mut().find(|(a, _)| a == action) {
+                bound.clone_from(keys);
+            }
+        }
+        keymap
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The first key of the action, for hints like `<Space> pause`.
+    pub fn hint(&self, action: Action) -> String {
+        match self.keys(action).first() {
+            Some(key) => format!("<{key}>"),
+            None => "<none>".to_string(),
+        }
+    }
+
+    /// Every action with the keys bound to it, in display order.
+    pub fn entries(&self) -> impl Iterator<Item = (Action, &[KeyBinding])> {
+        self.bindings
+            .iter()
+            .map(|(action, keys)| (*action, keys.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEventKind;
+
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent {
+            kind: KeyEventKind::Press,
+            ..KeyEvent::new(code, modifiers)
+        }
+    }
+
+    #[test]
+    fn parse_and_match_keys() {
+        let key = |key: &str| key.parse::<KeyBinding>().unwrap();
+        assert_eq!(key("Space"), KeyBinding::char(' '));
+        assert_eq!(key("ctrl-f"), KeyBinding::ctrl('f'));
+        assert_eq!(key("F9"), KeyBinding::new(KeyCode::F(9)));
+        assert_eq!(key("-"), KeyBinding::char('-'));
+        assert!("f13".parse::<KeyBinding>().is_err());
+        assert!("bogus".parse::<KeyBinding>().is_err());
+        assert_eq!(key("ctrl-pgdn").to_string(), "Ctrl-PgDn");
+
+        let keymap = Keymap::default();
+        let shift_x = press(KeyCode::Char('X'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&shift_x), Some(Action::Kill));
+        let ctrl_q = press(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&ctrl_q), None);
+    }
+
+    #[test]
+    fn rebind_over_preset() {
+        let config = KeysConfig {
+            preset: Some(KeymapPreset::Vim),
+            bind: BTreeMap::from([(Action::Quit, vec![KeyBinding::char('g')])]),
+        };
+        let keymap = Keymap::from_config(&config);
+        let g = press(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&g), Some(Action::Quit));
+        assert_eq!(keymap.keys(Action::First), [KeyBinding::new(KeyCode::Home)]);
+        let ctrl_f = press(KeyCode::Char('f'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&ctrl_f), Some(Action::PageDown));
+    }
+
+    #[test]
+    fn presets_bind_each_key_once() {
+        for preset in [KeymapPreset::Default, KeymapPreset::Vim, KeymapPreset::Htop] {
+            let keymap = Keymap::new(preset);
+            let mut seen: Vec<(&KeyBinding, Action)> = Vec::new();
+            for (action, keys) in keymap.entries() {
+                for key in keys {
+                    if let Some((_, other)) = seen.iter().find(|(bound, _)| *bound == key) {
+                        panic!("{preset:?} binds {key} to both {other:?} and {action:?}");
+                    }
+                    seen.push((key, action));
+                }
+            }
+        }
+    }
+}
@@ -1,12 +1,16 @@
 #![deny(clippy::enum_glob_use)]
 
+mod alerts;
 mod cli;
+mod config;
 mod display;
+mod keymap;
 mod network;
 mod os;
+mod usage;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::File,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -18,20 +22,23 @@ use std::{
 
 use clap::Parser;
 use crossterm::{
-    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{Event, KeyCode, KeyEventKind, KeyModifiers},
     terminal,
 };
-use display::{elapsed_time, RawTerminalBackend, Ui};
+use display::{elapsed_time, NetworkData, RawTerminalBackend, Theme, Ui};
 use eyre::bail;
+use log::warn;
 use network::{LocalSocket, Sniffer, Utilization};
 use pnet::datalink::{DataLinkReceiver, NetworkInterface};
 use ratatui::backend::{Backend, CrosstermBackend};
 use simplelog::WriteLogger;
 
-use crate::cli::Opt;
-use crate::os::ProcessInfo;
-
-const DISPLAY_DELTA: Duration = Duration::from_millis(1000);
+use crate::alerts::AlertRule;
+use crate::cli::{Command, Opt};
+use crate::config::Config;
+use crate::keymap::{Action, Keymap};
+use crate::os::{OrphanSocket, ProcessInfo, ProcessMeta, Signal};
+use crate::usage::{UsageKey, UsageStore, FLUSH_INTERVAL};
 
 fn main() -> eyre::Result<()> {
     let opts = Opt::parse();
@@ -49,14 +56,49 @@ fn main() -> eyre::Result<()> {
         )?;
     }
 
-    let os_input = os::get_input(opts.interface.as_deref())?;
+    if let Some(Command::Report(ref report)) = opts.command {
+        return usage::print_report(&opts, report);
+    }
+
+    if opts.only_mine && os::get_invoking_uid().is_none() {
+        bail!("Cannot determine the invoking user, which is required by --only-mine");
+    }
+
+    // the namespace has to be entered before any capture socket is opened
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    if let Some(ref netns) = opts.netns {
+        os::enter_netns(netns)?;
+    }
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    if opts.netns.is_some() || opts.all_netns {
+        bail!("Network namespaces are only supported on Linux");
+    }
+
+    let config = Config::load()?;
+    let theme = Theme::load(opts.render_opts.theme, &config)?;
+    let keymap = Keymap::from_config(&config.keys);
+    let alert_rules = config.alerts;
+    let usage = match opts.record {
+        true => Some(UsageStore::open(opts.db.as_deref())?),
+        false => None,
+    };
+
+    let os_input = os::get_input(opts.interface.as_deref(), opts.all_netns)?;
     if opts.raw {
         let terminal_backend = RawTerminalBackend {};
-        start(terminal_backend, os_input, opts);
+        start(
+            terminal_backend,
+            os_input,
+            opts,
+            theme,
+            keymap,
+            usage,
+            alert_rules,
+        );
     } else {
         let Ok(()) = terminal::enable_raw_mode() else {
             bail!(
-                "Failed to get stdout: if you are trying to pipe 'bandwhich' you should use the --raw flag"
+                "Failed to get stdout: if you are trying to pipe 'nethugs' you should use the --raw flag"
             )
         };
 
@@ -64,7 +106,15 @@ fn main() -> eyre::Result<()> {
         // Ignore enteralternatescreen error
         let _ = crossterm::execute!(&mut stdout, terminal::EnterAlternateScreen);
         let terminal_backend = CrosstermBackend::new(stdout);
-        start(terminal_backend, os_input, opts);
+        start(
+            terminal_backend,
+            os_input,
+            opts,
+            theme,
+            keymap,
+            usage,
+            alert_rules,
+        );
 
         // Ensure terminal is restored after exit (handles SIGINT case).
         // These operations are idempotent, so safe to call even if 'q' already cleaned up.
@@ -76,17 +126,28 @@ fn main() -> eyre::Result<()> {
 
 pub struct OpenSockets {
     sockets_to_procs: HashMap<LocalSocket, ProcessInfo>,
+    /// Sockets of other network namespaces, by namespace inode, with `--all-netns`.
+    netns_sockets: BTreeMap<u64, HashMap<LocalSocket, ProcessInfo>>,
+    orphan_sockets: HashMap<LocalSocket, OrphanSocket>,
+    processes: HashMap<ProcessInfo, ProcessMeta>,
 }
 
 pub struct OsInputOutput {
     pub interfaces_with_frames: Vec<(NetworkInterface, Box<dyn DataLinkReceiver>)>,
-    pub get_open_sockets: fn() -> OpenSockets,
+    pub get_open_sockets: Box<dyn FnMut() -> OpenSockets + Send>,
     pub terminal_events: Box<dyn Iterator<Item = Event> + Send>,
     pub write_to_stdout: Box<dyn FnMut(&str) + Send>,
 }
 
-pub fn start<B>(terminal_backend: B, os_input: OsInputOutput, opts: Opt)
-where
+pub fn start<B>(
+    terminal_backend: B,
+    os_input: OsInputOutput,
+    opts: Opt,
+    theme: Theme,
+    keymap: Keymap,
+    usage: Option<UsageStore>,
+    alert_rules: Vec<AlertRule>,
+) where
     B: Backend + Send + 'static,
 {
     let running = Arc::new(AtomicBool::new(true));
@@ -109,13 +170,32 @@ where
     let mut active_threads = vec![];
 
     let terminal_events = os_input.terminal_events;
-    let get_open_sockets = os_input.get_open_sockets;
+    let mut get_open_sockets = os_input.get_open_sockets;
     let mut write_to_stdout = os_input.write_to_stdout;
 
     let raw_mode = opts.raw;
 
     let network_utilization = Arc::new(Mutex::new(Utilization::new()));
-    let ui = Arc::new(Mutex::new(Ui::new(terminal_backend, &opts)));
+    let ui = {
+        let mut ui = Ui::new(terminal_backend, &opts, theme, keymap);
+        ui.set_local_ips(
+            os_input
+                .interfaces_with_frames
+                .iter()
+                .flat_map(|(interface, _)| interface.ips.iter().map(|network| network.ip()))
+                .collect(),
+        );
+        ui.set_alert_rules(alert_rules);
+        // so that `today` rules keep counting what earlier runs recorded
+        if let Some(ref usage) = usage {
+            let now = chrono::Local::now();
+            match usage.today(now) {
+                Ok(recorded) => ui.seed_alerts(now.date_naive(), &recorded),
+                Err(err) => warn!("Cannot read the usage recorded today: {err:#}"),
+            }
+        }
+        Arc::new(Mutex::new(ui))
+    };
 
     let display_handler = thread::Builder::new()
         .name("display_handler".to_string())
@@ -130,17 +210,23 @@ where
             let ui = ui.clone();
 
             move || {
+                let mut usage = usage;
+                let mut last_flush = Instant::now();
                 while running.load(Ordering::Acquire) {
                     let render_start_time = Instant::now();
                     let utilization = network_utilization.lock().unwrap().clone_and_reset();
-                    let OpenSockets { sockets_to_procs } = get_open_sockets();
-                    {
+                    let open_sockets = get_open_sockets();
+                    let (interval, pending_usage) = {
                         let mut ui = ui.lock().unwrap();
                         let paused = paused.load(Ordering::SeqCst);
                         let table_cycle_offset = table_cycle_offset.load(Ordering::SeqCst);
+                        ui.account(&open_sockets, &utilization);
                         if !paused {
-                            ui.update_state(sockets_to_procs, utilization);
+                            ui.update_state(open_sockets, utilization);
                         }
+                        let pending_usage = (usage.is_some()
+                            && last_flush.elapsed() >= FLUSH_INTERVAL)
+                            .then(|| ui.take_usage());
                         let elapsed_time = elapsed_time(
                             *last_start_time.read().unwrap(),
                             *cumulative_time.read().unwrap(),
@@ -152,15 +238,24 @@ where
                         } else {
                             ui.draw(paused, elapsed_time, table_cycle_offset);
                         }
+                        (ui.refresh_interval(), pending_usage)
+                    };
+                    // written without holding the lock, so that input is not held up
+                    if let (Some(usage), Some(pending_usage)) = (&mut usage, pending_usage) {
+                        flush_usage(usage, pending_usage);
+                        last_flush = Instant::now();
                     }
                     let render_duration = render_start_time.elapsed();
-                    if render_duration < DISPLAY_DELTA {
-                        park_timeout(DISPLAY_DELTA - render_duration);
+                    if render_duration < interval {
+                        park_timeout(interval - render_duration);
                     }
                 }
+                if let Some(ref mut usage) = usage {
+                    let pending_usage = ui.lock().unwrap().take_usage();
+                    flush_usage(usage, pending_usage);
+                }
                 if !raw_mode {
-                    let mut ui = ui.lock().unwrap();
-                    ui.end();
+                    ui.lock().unwrap().end();
                 }
             }
         })
@@ -180,25 +275,72 @@ where
                     };
                     let mut ui = ui.lock().unwrap();
 
-                    match evt {
-                        Event::Resize(_x, _y) if !raw_mode => {
-                            let paused = paused.load(Ordering::SeqCst);
-                            ui.draw(
+                    let redraw = |ui: &mut Ui<B>, table_cycle_offset: usize| {
+                        let paused = paused.load(Ordering::SeqCst);
+                        ui.draw(
+                            paused,
+                            elapsed_time(
+                                *last_start_time.read().unwrap(),
+                                *cumulative_time.read().unwrap(),
                                 paused,
-                                elapsed_time(
-                                    *last_start_time.read().unwrap(),
-                                    *cumulative_time.read().unwrap(),
-                                    paused,
-                                ),
-                                table_cycle_offset.load(Ordering::SeqCst),
-                            );
+                            ),
+                            table_cycle_offset,
+                        );
+                    };
+
+                    let key = match evt {
+                        Event::Resize(_x, _y) if !raw_mode => {
+                            redraw(&mut ui, table_cycle_offset.load(Ordering::SeqCst));
+                            continue;
+                        }
+                        Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                        _ => continue,
+                    };
+                    let action = ui.action(&key);
+
+                    if ui.is_showing_help() {
+                        match action {
+                            Some(Action::Up) => ui.scroll_help(-1),
+                            Some(Action::Down) => ui.scroll_help(1),
+                            _ => ui.toggle_help(),
+                        }
+                        redraw(&mut ui, table_cycle_offset.load(Ordering::SeqCst));
+                        continue;
+                    }
+
+                    if ui.is_prompting_signal() {
+                        match (action, key.code) {
+                            (Some(Action::Up), _) => ui.move_signal_cursor(-1),
+                            (Some(Action::Down), _) => ui.move_signal_cursor(1),
+                            (Some(Action::Open), _) => ui.choose_signal(),
+                            (_, KeyCode::Char('y') | KeyCode::Char('Y')) => ui.confirm_signal(),
+                            _ => ui.cancel_signal(),
                         }
-                        Event::Key(KeyEvent {
-                            modifiers: KeyModifiers::NONE,
-                            code: KeyCode::Char('q'),
-                            kind: KeyEventKind::Press,
-                            ..
-                        }) => {
+                        redraw(&mut ui, table_cycle_offset.load(Ordering::SeqCst));
+                        continue;
+                    }
+
+                    // while the filter is typed, keys are text rather than commands
+                    if ui.is_editing_filter() {
+                        if let KeyModifiers::NONE | KeyModifiers::SHIFT = key.modifiers {
+                            match key.code {
+                                KeyCode::Char(ch) => ui.push_filter_char(ch),
+                                KeyCode::Backspace => ui.pop_filter_char(),
+                                KeyCode::Enter => ui.confirm_filter(),
+                                KeyCode::Esc => ui.clear_filter(),
+                                _ => (),
+                            }
+                        }
+                        redraw(&mut ui, table_cycle_offset.load(Ordering::SeqCst));
+                        continue;
+                    }
+
+                    let Some(action) = action else {
+                        continue;
+                    };
+                    let mut offset = table_cycle_offset.load(Ordering::SeqCst);
+                    match action {
+                        Action::Quit => {
                             running.store(false, Ordering::Release);
                             display_handler.unpark();
                             match terminal::disable_raw_mode() {
@@ -213,14 +355,10 @@ where
                             };
                             break;
                         }
-                        Event::Key(KeyEvent {
-                            modifiers: KeyModifiers::NONE,
-                            code: KeyCode::Char(' '),
-                            kind: KeyEventKind::Press,
-                            ..
-                        }) => {
+                        Action::Pause => {
                             let restarting = paused.fetch_xor(true, Ordering::SeqCst);
                             if restarting {
+                                ui.clear_history_cursor();
                                 *last_start_time.write().unwrap() = Instant::now();
                             } else {
                                 let last_start_time_copy = *last_start_time.read().unwrap();
@@ -231,26 +369,54 @@ where
                             }
 
                             display_handler.unpark();
+                            continue;
                         }
-                        Event::Key(KeyEvent {
-                            modifiers: KeyModifiers::NONE,
-                            code: KeyCode::Tab,
-                            kind: KeyEventKind::Press,
-                            ..
-                        }) => {
-                            let paused = paused.load(Ordering::SeqCst);
-                            let elapsed_time = elapsed_time(
-                                *last_start_time.read().unwrap(),
-                                *cumulative_time.read().unwrap(),
-                                paused,
-                            );
-                            let table_count = ui.get_table_count();
-                            let new = table_cycle_offset.load(Ordering::SeqCst) + 1 % table_count;
-                            table_cycle_offset.store(new, Ordering::SeqCst);
-                            ui.draw(paused, elapsed_time, new);
+                        Action::NextTable => {
+                            offset = (offset + 1) % ui.get_table_count();
+                            table_cycle_offset.store(offset, Ordering::SeqCst);
                         }
-                        _ => (),
-                    };
+                        // the history only stands still while paused
+                        Action::Earlier if paused.load(Ordering::SeqCst) => {
+                            ui.move_history_cursor(1)
+                        }
+                        Action::Later if paused.load(Ordering::SeqCst) => {
+                            ui.move_history_cursor(-1)
+                        }
+                        Action::Earlier | Action::Later => continue,
+                        Action::Help => ui.toggle_help(),
+                        Action::TotalChart => ui.toggle_total_chart(),
+                        Action::SlowerRefresh | Action::FasterRefresh => {
+                            ui.step_interval(match action {
+                                Action::SlowerRefresh => 1,
+                                _ => -1,
+                            });
+                            // start the new interval right away
+                            display_handler.unpark();
+                        }
+                        Action::LongerHistory => ui.step_history_length(1),
+                        Action::ShorterHistory => ui.step_history_length(-1),
+                        Action::ChartStyle => ui.cycle_chart_style(),
+                        Action::ChartScale => ui.cycle_chart_scale(),
+                        Action::LogScale => ui.toggle_log_scale(),
+                        Action::Up => ui.move_selection(offset, -1),
+                        Action::Down => ui.move_selection(offset, 1),
+                        Action::PageUp => ui.move_page(offset, -1),
+                        Action::PageDown => ui.move_page(offset, 1),
+                        Action::First => ui.select_first(offset),
+                        Action::Last => ui.select_last(offset),
+                        Action::Open => ui.open_detail(offset),
+                        Action::Back => ui.back(),
+                        Action::Group => ui.cycle_group_by(),
+                        Action::Collapse => ui.toggle_collapse(),
+                        Action::CollapseAll => ui.toggle_collapse_all(),
+                        Action::Sort => ui.cycle_sort_by(),
+                        Action::Reverse => ui.toggle_reverse(),
+                        Action::Filter => ui.start_filter(),
+                        Action::Terminate => ui.prompt_signal(offset, Some(Signal::Term)),
+                        Action::Kill => ui.prompt_signal(offset, Some(Signal::Kill)),
+                        Action::Signal => ui.prompt_signal(offset, None),
+                    }
+                    redraw(&mut ui, offset);
                 }
             }
         })
@@ -287,3 +453,13 @@ where
         thread_handler.join().unwrap()
     }
 }
+
+/// Writes the traffic tallied since the last flush to the usage database.
+fn flush_usage(store: &mut UsageStore, usage: HashMap<UsageKey, NetworkData>) {
+    if usage.is_empty() {
+        return;
+    }
+    if let Err(err) = store.record(chrono::Local::now(), &usage) {
+        warn!("Cannot record usage: {err:#}");
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
+    collections::HashMap,
     fmt,
-    net::{IpAddr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
 };
 
 #[derive(PartialEq, Hash, Eq, Clone, PartialOrd, Ord, Debug, Copy)]
@@ -62,6 +63,42 @@ impl fmt::Debug for LocalSocket {
     }
 }
 
+impl LocalSocket {
+    /// Looks up the entry of a map that belongs to this socket.
+    ///
+    /// Besides a direct match, this tries the other forms the owning socket may be bound
+    /// as: its IPv4-mapped counterpart, and the unspecified addresses.
+    pub fn find_in<'a, T>(&self, sockets: &'a HashMap<LocalSocket, T>) -> Option<&'a T> {
+        sockets
+            // direct match
+            .get(self)
+            // IPv4-mapped IPv6 addresses
+            .or_else(|| {
+                let swapped: IpAddr = match self.ip {
+                    IpAddr::V4(v4) => v4.to_ipv6_mapped().into(),
+                    IpAddr::V6(v6) => v6.to_ipv4_mapped()?.into(),
+                };
+                sockets.get(&LocalSocket {
+                    ip: swapped,
+                    ..*self
+                })
+            })
+            // address unspecified
+            .or_else(|| {
+                sockets.get(&LocalSocket {
+                    ip: Ipv4Addr::UNSPECIFIED.into(),
+                    ..*self
+                })
+            })
+            .or_else(|| {
+                sockets.get(&LocalSocket {
+                    ip: Ipv6Addr::UNSPECIFIED.into(),
+                    ..*self
+                })
+            })
+    }
+}
+
 #[derive(PartialEq, Hash, Eq, Clone, PartialOrd, Ord, Copy)]
 pub struct Connection {
     pub remote_socket: Socket,
@@ -98,3 +135,29 @@ impl Connection {
         }
     }
 }
+
+/// Returns the host name of an address if it has been resolved, or the address itself.
+pub fn display_ip_or_host(ip: IpAddr, ip_to_host: &HashMap<IpAddr, String>) -> String {
+    match ip_to_host.get(&ip) {
+        Some(host) => host.clone(),
+        None => ip.to_string(),
+    }
+}
+
+pub fn display_connection_string(
+    connection: &Connection,
+    ip_to_host: &HashMap<IpAddr, String>,
+) -> String {
+    let Connection {
+        remote_socket,
+        local_socket,
+    } = connection;
+    format!(
+        "{}:{} ⇄ {}:{} ({})",
+        local_socket.ip,
+        local_socket.port,
+        display_ip_or_host(remote_socket.ip, ip_to_host),
+        remote_socket.port,
+        local_socket.protocol,
+    )
+}
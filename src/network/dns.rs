@@ -0,0 +1,67 @@
+use std::{
+    collections::HashSet,
+    net::IpAddr,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use dns_lookup::lookup_addr;
+use log::debug;
+
+/// Resolves IP addresses to host names on a background thread, using reverse DNS.
+///
+/// Lookups are best-effort: addresses without a PTR record are never reported.
+pub struct Resolver {
+    requested: HashSet<IpAddr>,
+    requests: Sender<IpAddr>,
+    resolved: Receiver<(IpAddr, String)>,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        let (requests, pending) = mpsc::channel::<IpAddr>();
+        let (results, resolved) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("dns_resolver".to_string())
+            .spawn(move || {
+                // ends once the resolver is dropped
+                for ip in pending {
+                    match lookup_addr(&ip) {
+                        Ok(host) if host != ip.to_string() => {
+                            if results.send((ip, host)).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(_) => (),
+                        Err(err) => debug!("Cannot resolve {ip}: {err}"),
+                    }
+                }
+            })
+            .unwrap();
+
+        Resolver {
+            requested: HashSet::new(),
+            requests,
+            resolved,
+        }
+    }
+
+    /// Queues an address for resolution, unless it has been queued before.
+    pub fn resolve(&mut self, ip: IpAddr) {
+        if self.requested.insert(ip) {
+            let _ = self.requests.send(ip);
+        }
+    }
+
+    /// Forgets the addresses that `keep` rejects, so that they are queued again if they
+    /// come back.
+    pub fn retain(&mut self, keep: impl Fn(&IpAddr) -> bool) {
+        self.requested.retain(keep);
+    }
+
+    /// Returns the host names resolved since the last call.
+    pub fn take_resolved(&self) -> impl Iterator<Item = (IpAddr, String)> + '_ {
+        self.resolved.try_iter()
+    }
+}
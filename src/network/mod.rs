@@ -1,7 +1,9 @@
 mod connection;
+mod dns;
 mod sniffer;
 mod utilization;
 
 pub use connection::*;
+pub use dns::*;
 pub use sniffer::*;
 pub use utilization::*;
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use crate::network::{Connection, Direction, Segment};
 
@@ -11,15 +14,26 @@ pub struct ConnectionInfo {
 #[derive(Clone)]
 pub struct Utilization {
     pub connections: HashMap<Connection, ConnectionInfo>,
+    /// How long the traffic was collected for, measured when it is taken with
+    /// `clone_and_reset` rather than assumed from the refresh interval.
+    pub elapsed: Duration,
+    started: Instant,
 }
 
 impl Utilization {
     pub fn new() -> Self {
         let connections = HashMap::new();
-        Utilization { connections }
+        Utilization {
+            connections,
+            elapsed: Duration::ZERO,
+            started: Instant::now(),
+        }
     }
     pub fn clone_and_reset(&mut self) -> Self {
-        let clone = self.clone();
+        let mut clone = self.clone();
+        let now = Instant::now();
+        clone.elapsed = now - self.started;
+        self.started = now;
         self.connections.clear();
         clone
     }
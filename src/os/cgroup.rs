@@ -0,0 +1,121 @@
+use procfs::process::Process;
+
+/// Suffixes of systemd units that can own processes.
+const UNIT_SUFFIXES: [&str; 5] = [".service", ".scope", ".socket", ".mount", ".swap"];
+
+/// Length of a full docker/podman/containerd container ID.
+const CONTAINER_ID_LEN: usize = 64;
+
+/// Length of the abbreviated container ID, as shown by `docker ps`.
+const SHORT_CONTAINER_ID_LEN: usize = 12;
+
+/// Returns the cgroup v2 path of a process, e.g. `/system.slice/sshd.service`.
+///
+/// Falls back to the `name=systemd` hierarchy on legacy (v1-only) hosts.
+pub fn get_cgroup_path(process: &Process) -> Option<String> {
+    let cgroups = process.cgroups().ok()?.0;
+    cgroups
+        .iter()
+        .find(|cg| cg.hierarchy == 0 && cg.controllers.is_empty())
+        .or_else(|| {
+            cgroups
+                .iter()
+                .find(|cg| cg.controllers.iter().any(|c| c == "name=systemd"))
+        })
+        .map(|cg| cg.pathname.clone())
+}
+
+/// Extracts the systemd unit owning a cgroup path.
+///
+/// The innermost unit wins, so that user services nested in `user@.service` are
+/// reported on their own. If there is no unit, the innermost slice is used instead.
+pub fn unit_from_cgroup_path(path: &str) -> Option<String> {
+    let mut components = path.split('/').rev();
+    components
+        .clone()
+        .find(|c| UNIT_SUFFIXES.iter().any(|suffix| c.ends_with(suffix)))
+        .or_else(|| components.find(|c| c.ends_with(".slice")))
+        .map(str::to_string)
+}
+
+/// Extracts the (abbreviated) container ID from a cgroup path.
+///
+/// Handles both the systemd cgroup driver (`docker-<id>.scope`, `libpod-<id>.scope`,
+/// `cri-containerd-<id>.scope`, `crio-<id>.scope`) and the cgroupfs driver
+/// (`/docker/<id>`, `/kubepods/burstable/pod<uid>/<id>`).
+pub fn container_from_cgroup_path(path: &str) -> Option<String> {
+    path.split('/').rev().find_map(|component| {
+        let component = component.strip_suffix(".scope").unwrap_or(component);
+        let id = component.rsplit('-').next()?;
+        (id.len() == CONTAINER_ID_LEN && id.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| id[..SHORT_CONTAINER_ID_LEN].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER_ID: &str = "3f2a1b9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f1a";
+
+    #[test]
+    fn unit_of_system_service() {
+        assert_eq!(
+            unit_from_cgroup_path("/system.slice/sshd.service").as_deref(),
+            Some("sshd.service")
+        );
+    }
+
+    #[test]
+    fn unit_of_nested_user_service() {
+        let path = "/user.slice/user-1000.slice/user@1000.service/app.slice/pipewire.service";
+        assert_eq!(
+            unit_from_cgroup_path(path).as_deref(),
+            Some("pipewire.service")
+        );
+    }
+
+    #[test]
+    fn unit_falls_back_to_slice() {
+        assert_eq!(
+            unit_from_cgroup_path("/user.slice/user-1000.slice").as_deref(),
+            Some("user-1000.slice")
+        );
+        assert_eq!(unit_from_cgroup_path("/"), None);
+    }
+
+    #[test]
+    fn container_from_systemd_driver() {
+        for prefix in ["docker", "libpod", "cri-containerd", "crio"] {
+            let path = format!("/system.slice/{prefix}-{CONTAINER_ID}.scope");
+            assert_eq!(
+                container_from_cgroup_path(&path).as_deref(),
+                Some(&CONTAINER_ID[..12])
+            );
+        }
+    }
+
+    #[test]
+    fn container_from_kubepods() {
+        let path = format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod0b7e.slice/cri-containerd-{CONTAINER_ID}.scope"
+        );
+        assert_eq!(
+            container_from_cgroup_path(&path).as_deref(),
+            Some(&CONTAINER_ID[..12])
+        );
+    }
+
+    #[test]
+    fn container_from_cgroupfs_driver() {
+        let path = format!("/docker/{CONTAINER_ID}");
+        assert_eq!(
+            container_from_cgroup_path(&path).as_deref(),
+            Some(&CONTAINER_ID[..12])
+        );
+        assert_eq!(
+            container_from_cgroup_path("/system.slice/sshd.service"),
+            None
+        );
+    }
+}
@@ -1,51 +1,222 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
 
-use procfs::process::FDTarget;
+use log::debug;
+use procfs::{
+    net::TcpState,
+    process::{FDTarget, Process},
+};
 
 use crate::{
     network::{LocalSocket, Protocol},
-    os::ProcessInfo,
+    os::{
+        cgroup::{container_from_cgroup_path, get_cgroup_path, unit_from_cgroup_path},
+        netns::{named_netns, netns_inode, netns_label},
+        sock_diag, OrphanSocket, ProcessInfo, ProcessMeta,
+    },
     OpenSockets,
 };
 
-pub(crate) fn get_open_sockets() -> OpenSockets {
+/// How long the sockets of other network namespaces are reused for, when they only
+/// serve to explain unattributed traffic.
+const FOREIGN_SOCKETS_TTL: Duration = Duration::from_secs(5);
+
+/// What is kept between scans, so that namespaces are not looked up on every tick.
+#[derive(Default)]
+pub(crate) struct NetnsCache {
+    /// The network namespace of each process, looked up once.
+    inodes: HashMap<ProcessInfo, Option<u64>>,
+    /// The sockets of other namespaces as orphans, and when they were read.
+    orphans: Option<(Instant, HashMap<LocalSocket, OrphanSocket>)>,
+}
+
+/// A row of the kernel's socket tables.
+pub(super) struct SocketEntry {
+    pub socket: LocalSocket,
+    pub inode: u64,
+    /// Whether the socket is held by the kernel rather than by a process.
+    pub kernel: bool,
+}
+
+/// Reads the socket tables of our own network namespace through `sock_diag`, or from
+/// `/proc/net` where that fails, e.g. without the `udp_diag` module. The tables of the
+/// namespace `process` is in are always read from `/proc`.
+fn read_socket_entries(process: Option<&Process>) -> Vec<SocketEntry> {
+    let diag = |protocol| match process {
+        Some(_) => None,
+        None => sock_diag::socket_entries(protocol)
+            .inspect_err(|err| debug!("Cannot list {protocol} sockets with sock_diag: {err}"))
+            .ok(),
+    };
+
+    macro_rules! entries {
+        ($source: expr, $proto: expr, $is_kernel: expr) => {
+            $source
+                .into_iter()
+                .filter_map(|res| res.ok())
+                .flatten()
+                .map(|entry| SocketEntry {
+                    socket: LocalSocket {
+                        ip: entry.local_address.ip(),
+                        port: entry.local_address.port(),
+                        protocol: $proto,
+                    },
+                    inode: entry.inode,
+                    kernel: $is_kernel(&entry),
+                })
+                .collect::<Vec<_>>()
+        };
+    }
+
+    let tcp = diag(Protocol::Tcp).unwrap_or_else(|| {
+        let tables = match process {
+            Some(process) => [process.tcp(), process.tcp6()],
+            None => [procfs::net::tcp(), procfs::net::tcp6()],
+        };
+        // sockets in TIME_WAIT have no inode either, but they were closed by a process
+        entries!(tables, Protocol::Tcp, |entry: &procfs::net::TcpNetEntry| {
+            entry.inode == 0 && entry.state != TcpState::TimeWait
+        })
+    });
+    let udp = diag(Protocol::Udp).unwrap_or_else(|| {
+        let tables = match process {
+            Some(process) => [process.udp(), process.udp6()],
+            None => [procfs::net::udp(), procfs::net::udp6()],
+        };
+        entries!(tables, Protocol::Udp, |entry: &procfs::net::UdpNetEntry| {
+            entry.inode == 0
+        })
+    });
+    tcp.into_iter().chain(udp).collect()
+}
+
+/// Maps open sockets to processes.
+///
+/// With `all_netns`, sockets of every network namespace are resolved, and processes
+/// outside of our own namespace are labelled with theirs. Otherwise, sockets of other
+/// namespaces are only reported as orphans, and only read every few seconds.
+pub(crate) fn get_open_sockets(all_netns: bool, cache: &mut NetnsCache) -> OpenSockets {
     let mut open_sockets = HashMap::new();
+    let mut netns_sockets: BTreeMap<u64, HashMap<_, _>> = BTreeMap::new();
+    let mut orphan_sockets = HashMap::new();
     let mut inode_to_proc = HashMap::new();
+    let mut processes = HashMap::new();
+    // one process per foreign namespace, through which its socket tables are read
+    let mut netns_members = HashMap::new();
+    let own_netns = netns_inode(std::process::id());
+    let netns_names = named_netns();
 
     if let Ok(all_procs) = procfs::process::all_processes() {
         for process in all_procs.filter_map(|res| res.ok()) {
-            let Ok(fds) = process.fd() else { continue };
             let Ok(stat) = process.stat() else { continue };
             let proc_name = stat.comm;
-            let proc_info = ProcessInfo::new(&proc_name, stat.pid as u32);
-            for fd in fds.filter_map(|res| res.ok()) {
-                if let FDTarget::Socket(inode) = fd.target {
-                    inode_to_proc.insert(inode, proc_info.clone());
+            let proc_info =
+                ProcessInfo::new(&proc_name, stat.pid as u32).with_start_time(stat.starttime);
+            // parent links are kept for every process, so that the process tree can
+            // be walked through ancestors without any network activity, and so are
+            // executables, so that a process that closed its sockets before exiting
+            // can still be matched with its restart
+            let mut meta = ProcessMeta {
+                ppid: u32::try_from(stat.ppid).ok(),
+                exe: process.exe().ok().map(|exe| exe.display().to_string()),
+                ..Default::default()
+            };
+            let netns = *cache
+                .inodes
+                .entry(proc_info.clone())
+                .or_insert_with(|| netns_inode(proc_info.pid));
+            if let Some(inode) = netns.filter(|&ns| Some(ns) != own_netns) {
+                netns_members.entry(inode).or_insert(process.pid);
+                if all_netns {
+                    meta.netns = Some(netns_label(inode, &netns_names));
+                }
+            }
+            let mut owns_sockets = false;
+            if let Ok(fds) = process.fd() {
+                for fd in fds.filter_map(|res| res.ok()) {
+                    if let FDTarget::Socket(inode) = fd.target {
+                        inode_to_proc.insert(inode, proc_info.clone());
+                        owns_sockets = true;
+                    }
                 }
             }
+            if owns_sockets {
+                let cgroup = get_cgroup_path(&process);
+                meta.unit = cgroup.as_deref().and_then(unit_from_cgroup_path);
+                meta.container = cgroup.as_deref().and_then(container_from_cgroup_path);
+                meta.cgroup = cgroup;
+                meta.uid = process.uid().ok();
+                meta.cmdline = process
+                    .cmdline()
+                    .ok()
+                    .filter(|args| !args.is_empty())
+                    .map(|args| args.join(" "));
+            }
+            processes.insert(proc_info, meta);
         }
     }
 
-    macro_rules! insert_proto {
-        ($source: expr, $proto: expr) => {
-            let entries = $source.into_iter().filter_map(|res| res.ok()).flatten();
-            for entry in entries {
-                if let Some(proc_info) = inode_to_proc.get(&entry.inode) {
-                    let socket = LocalSocket {
-                        ip: entry.local_address.ip(),
-                        port: entry.local_address.port(),
-                        protocol: $proto,
-                    };
-                    open_sockets.insert(socket, proc_info.clone());
-                }
+    cache
+        .inodes
+        .retain(|proc_info, _| processes.contains_key(proc_info));
+
+    let mut entries = read_socket_entries(None)
+        .into_iter()
+        .map(|entry| (None, entry))
+        .collect::<Vec<_>>();
+    if all_netns {
+        for (inode, pid) in netns_members {
+            let Ok(process) = Process::new(pid) else {
+                continue;
+            };
+            let foreign_entries = read_socket_entries(Some(&process));
+            entries.extend(
+                foreign_entries
+                    .into_iter()
+                    .map(|entry| (Some(inode), entry)),
+            );
+        }
+    } else {
+        let fresh = |(read_at, _): &(Instant, _)| read_at.elapsed() < FOREIGN_SOCKETS_TTL;
+        if !cache.orphans.as_ref().is_some_and(fresh) {
+            let mut foreign_orphans = HashMap::new();
+            for (inode, pid) in netns_members {
+                let Ok(process) = Process::new(pid) else {
+                    continue;
+                };
+                let label = netns_label(inode, &netns_names);
+                foreign_orphans.extend(
+                    read_socket_entries(Some(&process))
+                        .into_iter()
+                        .map(|entry| (entry.socket, OrphanSocket::Netns(label.clone()))),
+                );
             }
-        };
+            cache.orphans = Some((Instant::now(), foreign_orphans));
+        }
+        if let Some((_, foreign_orphans)) = &cache.orphans {
+            orphan_sockets.extend(foreign_orphans.clone());
+        }
     }
 
-    insert_proto!([procfs::net::tcp(), procfs::net::tcp6()], Protocol::Tcp);
-    insert_proto!([procfs::net::udp(), procfs::net::udp6()], Protocol::Udp);
+    for (netns, entry) in entries {
+        if let Some(proc_info) = inode_to_proc.get(&entry.inode) {
+            // the same address may be bound in several namespaces
+            let sockets = match netns {
+                Some(netns) => netns_sockets.entry(netns).or_default(),
+                None => &mut open_sockets,
+            };
+            sockets.insert(entry.socket, proc_info.clone());
+        } else if entry.kernel {
+            orphan_sockets.insert(entry.socket, OrphanSocket::Kernel);
+        }
+    }
 
     OpenSockets {
         sockets_to_procs: open_sockets,
+        netns_sockets,
+        orphan_sockets,
+        processes,
     }
 }
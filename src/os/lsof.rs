@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap};
+
 use crate::{os::lsof_utils::get_connections, OpenSockets};
 
 pub(crate) fn get_open_sockets() -> OpenSockets {
@@ -5,5 +7,10 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
         .filter_map(|raw| raw.as_local_socket().map(|s| (s, raw.proc_info)))
         .collect();
 
-    OpenSockets { sockets_to_procs }
+    OpenSockets {
+        sockets_to_procs,
+        netns_sockets: BTreeMap::new(),
+        orphan_sockets: HashMap::new(),
+        processes: HashMap::new(),
+    }
 }
@@ -1,5 +1,11 @@
 #[cfg(any(target_os = "android", target_os = "linux"))]
+mod cgroup;
+#[cfg(any(target_os = "android", target_os = "linux"))]
 mod linux;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod netns;
+#[cfg(any(target_os = "android", target_os = "linux"))]
+mod sock_diag;
 
 #[cfg(any(target_os = "macos", target_os = "freebsd"))]
 mod lsof;
@@ -12,5 +18,11 @@ mod windows;
 
 mod errors;
 pub(crate) mod shared;
+mod signal;
+mod users;
 
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use netns::enter_netns;
 pub use shared::*;
+pub use signal::*;
+pub use users::*;
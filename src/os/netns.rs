@@ -0,0 +1,65 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io,
+    os::{fd::AsRawFd, unix::fs::MetadataExt},
+    path::PathBuf,
+};
+
+use eyre::{eyre, WrapErr};
+
+/// Where `ip netns` keeps its named namespaces.
+const NAMED_NETNS_DIR: &str = "/run/netns";
+
+/// Resolves a namespace given as a PID or as a name known to `ip netns`.
+fn netns_path(netns: &str) -> PathBuf {
+    match netns.parse::<u32>() {
+        Ok(pid) => PathBuf::from(format!("/proc/{pid}/ns/net")),
+        Err(_) => PathBuf::from(NAMED_NETNS_DIR).join(netns),
+    }
+}
+
+/// Moves the calling thread into another network namespace.
+///
+/// This must be done before any capture socket is opened and before any other
+/// thread is spawned, since both inherit the namespace at creation time.
+pub fn enter_netns(netns: &str) -> eyre::Result<()> {
+    let path = netns_path(netns);
+    let file = File::open(&path)
+        .wrap_err_with(|| format!("Cannot open network namespace {}", path.display()))?;
+    // SAFETY: the file descriptor is valid for the duration of the call
+    if unsafe { libc::setns(file.as_raw_fd(), libc::CLONE_NEWNET) } != 0 {
+        return Err(eyre!(io::Error::last_os_error()))
+            .wrap_err_with(|| format!("Cannot enter network namespace {netns}"));
+    }
+    Ok(())
+}
+
+/// Returns the inode identifying the network namespace of a process.
+pub fn netns_inode(pid: u32) -> Option<u64> {
+    fs::metadata(format!("/proc/{pid}/ns/net"))
+        .ok()
+        .map(|meta| meta.ino())
+}
+
+/// Returns the names given to network namespaces by `ip netns`, by inode.
+pub fn named_netns() -> HashMap<u64, String> {
+    let Ok(entries) = fs::read_dir(NAMED_NETNS_DIR) else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let inode = fs::metadata(entry.path()).ok()?.ino();
+            Some((inode, entry.file_name().to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+/// Returns a human readable label for a network namespace.
+pub fn netns_label(inode: u64, names: &HashMap<u64, String>) -> String {
+    names
+        .get(&inode)
+        .cloned()
+        .unwrap_or_else(|| format!("net:[{inode}]"))
+}
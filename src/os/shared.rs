@@ -11,7 +11,7 @@ use log::{debug, warn};
 use pnet::datalink::{self, Channel::Ethernet, Config, DataLinkReceiver, NetworkInterface};
 
 #[cfg(any(target_os = "android", target_os = "linux"))]
-use crate::os::linux::get_open_sockets;
+use crate::os::linux::{get_open_sockets, NetnsCache};
 #[cfg(any(target_os = "macos", target_os = "freebsd"))]
 use crate::os::lsof::get_open_sockets;
 #[cfg(target_os = "windows")]
@@ -21,6 +21,9 @@ use crate::os::windows::get_open_sockets;
 pub struct ProcessInfo {
     pub name: String,
     pub pid: u32,
+    /// When the process started, in platform specific units, or 0 if unknown.
+    /// Tells apart unrelated processes that were given the same PID.
+    pub start_time: u64,
 }
 
 impl ProcessInfo {
@@ -28,8 +31,44 @@ impl ProcessInfo {
         Self {
             name: name.to_string(),
             pid,
+            start_time: 0,
         }
     }
+
+    pub fn with_start_time(mut self, start_time: u64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+}
+
+/// The origin of a socket that has no owning process.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrphanSocket {
+    /// The socket is held by the kernel itself, e.g. for NFS or WireGuard.
+    Kernel,
+    /// The socket lives in another network namespace, which is not monitored.
+    Netns(String),
+}
+
+/// Extra process attributes used to aggregate traffic, where the platform provides them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcessMeta {
+    /// The cgroup v2 path, e.g. `/system.slice/sshd.service`.
+    pub cgroup: Option<String>,
+    /// The systemd unit (or slice) the process belongs to.
+    pub unit: Option<String>,
+    /// The abbreviated ID of the container the process runs in.
+    pub container: Option<String>,
+    /// The UID the process runs as.
+    pub uid: Option<u32>,
+    /// The PID of the parent process.
+    pub ppid: Option<u32>,
+    /// The network namespace of the process, if it differs from ours.
+    pub netns: Option<String>,
+    /// The full command line.
+    pub cmdline: Option<String>,
+    /// The path of the executable.
+    pub exe: Option<String>,
 }
 
 /// Poll timeout for terminal events.
@@ -93,7 +132,7 @@ fn create_write_to_stdout() -> Box<dyn FnMut(&str) + Send> {
         move |output| match writeln!(stdout, "{output}") {
             Ok(_) => (),
             Err(e) if e.kind() == ErrorKind::BrokenPipe => {
-                // A process that was listening to bandwhich stdout has exited
+                // A process that was listening to nethugs stdout has exited
                 // We can't do much here, lets just exit as well
                 std::process::exit(0)
             }
@@ -102,7 +141,7 @@ fn create_write_to_stdout() -> Box<dyn FnMut(&str) + Send> {
     })
 }
 
-pub fn get_input(interface_name: Option<&str>) -> eyre::Result<OsInputOutput> {
+pub fn get_input(interface_name: Option<&str>, all_netns: bool) -> eyre::Result<OsInputOutput> {
     // get the user's requested interface, if any
     // IDEA: allow requesting multiple interfaces
     let requested_interfaces = interface_name
@@ -200,6 +239,18 @@ pub fn get_input(interface_name: Option<&str>) -> eyre::Result<OsInputOutput> {
 
     let write_to_stdout = create_write_to_stdout();
 
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    let get_open_sockets = {
+        let mut cache = NetnsCache::default();
+        Box::new(move || get_open_sockets(all_netns, &mut cache))
+    };
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    let get_open_sockets = {
+        // namespaces are a Linux concept
+        let _ = all_netns;
+        Box::new(get_open_sockets)
+    };
+
     Ok(OsInputOutput {
         interfaces_with_frames,
         get_open_sockets,
@@ -221,9 +272,9 @@ fn eperm_message() -> &'static str {
     Insufficient permissions to listen on network interface(s). You can work around
     this issue like this:
 
-    * Try running `bandwhich` with `sudo`
+    * Try running `nethugs` with `sudo`
 
-    * Build a `setcap(8)` wrapper for `bandwhich` with the following rules:
+    * Build a `setcap(8)` wrapper for `nethugs` with the following rules:
         `cap_sys_ptrace,cap_dac_read_search,cap_net_raw,cap_net_admin+ep`
     "#
 }
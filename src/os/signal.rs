@@ -0,0 +1,116 @@
+use std::{fmt, io};
+
+use crate::os::ProcessInfo;
+
+/// Signals that can be sent to a process from the UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Term,
+    Kill,
+    Int,
+    Hup,
+    Stop,
+    Cont,
+    Usr1,
+    Usr2,
+}
+
+impl Signal {
+    /// The signals offered by the picker, most useful first.
+    pub const ALL: [Signal; 8] = [
+        Signal::Term,
+        Signal::Kill,
+        Signal::Int,
+        Signal::Hup,
+        Signal::Stop,
+        Signal::Cont,
+        Signal::Usr1,
+        Signal::Usr2,
+    ];
+
+    #[cfg(unix)]
+    fn number(self) -> libc::c_int {
+        match self {
+            Signal::Term => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Int => libc::SIGINT,
+            Signal::Hup => libc::SIGHUP,
+            Signal::Stop => libc::SIGSTOP,
+            Signal::Cont => libc::SIGCONT,
+            Signal::Usr1 => libc::SIGUSR1,
+            Signal::Usr2 => libc::SIGUSR2,
+        }
+    }
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Signal::Term => "SIGTERM",
+            Signal::Kill => "SIGKILL",
+            Signal::Int => "SIGINT",
+            Signal::Hup => "SIGHUP",
+            Signal::Stop => "SIGSTOP",
+            Signal::Cont => "SIGCONT",
+            Signal::Usr1 => "SIGUSR1",
+            Signal::Usr2 => "SIGUSR2",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Whether the process is still running, rather than another one that was given its PID.
+///
+/// Only Linux reports start times precisely enough to tell, elsewhere this is assumed.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub fn is_running(proc_info: &ProcessInfo) -> bool {
+    if proc_info.start_time == 0 {
+        return true;
+    }
+    let Ok(pid) = i32::try_from(proc_info.pid) else {
+        return false;
+    };
+    procfs::process::Process::new(pid)
+        .and_then(|process| process.stat())
+        .is_ok_and(|stat| stat.starttime == proc_info.start_time)
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+pub fn is_running(_proc_info: &ProcessInfo) -> bool {
+    true
+}
+
+#[cfg(unix)]
+pub fn send_signal(pid: u32, signal: Signal) -> io::Result<()> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "PID out of range"))?;
+    // SAFETY: kill has no memory safety requirements
+    match unsafe { libc::kill(pid, signal.number()) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn send_signal(_pid: u32, _signal: Signal) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "signals are not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    fn tell_reused_pids_apart() {
+        let pid = std::process::id();
+        let stat = procfs::process::Process::myself().unwrap().stat().unwrap();
+        let myself = ProcessInfo::new("nethugs", pid).with_start_time(stat.starttime);
+        assert!(is_running(&myself));
+        let predecessor = ProcessInfo::new("nethugs", pid).with_start_time(stat.starttime - 1);
+        assert!(!is_running(&predecessor));
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    io, mem,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+use crate::{
+    network::{LocalSocket, Protocol},
+    os::linux::SocketEntry,
+};
+
+/// `SOCK_DIAG_BY_FAMILY` of `linux/sock_diag.h`.
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+/// `TCP_TIME_WAIT` of `netinet/tcp.h`.
+const TCP_TIME_WAIT: u8 = 6;
+/// The size of `struct nlmsghdr`.
+const HEADER_LEN: usize = 16;
+/// The size of `struct inet_diag_req_v2`.
+const REQUEST_LEN: usize = 56;
+/// The size of `struct inet_diag_msg`.
+const MESSAGE_LEN: usize = 72;
+
+/// Lists the sockets of a protocol in our network namespace through the kernel's
+/// `sock_diag` netlink interface, which is much cheaper than parsing `/proc/net` on
+/// hosts with many sockets.
+pub fn socket_entries(protocol: Protocol) -> io::Result<Vec<SocketEntry>> {
+    // SAFETY: a plain socket call, whose result is checked before use
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the descriptor was just opened and is owned by nobody else
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    let mut entries = Vec::new();
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        request_dump(&socket, family as u8, protocol)?;
+        receive_dump(&socket, protocol, &mut entries)?;
+    }
+    Ok(entries)
+}
+
+/// Asks for every socket of a family and protocol, in any state.
+fn request_dump(socket: &OwnedFd, family: u8, protocol: Protocol) -> io::Result<()> {
+    let ip_protocol = match protocol {
+        Protocol::Tcp => libc::IPPROTO_TCP,
+        Protocol::Udp => libc::IPPROTO_UDP,
+    };
+    let mut request = Vec::with_capacity(HEADER_LEN + REQUEST_LEN);
+    // struct nlmsghdr
+    request.extend(((HEADER_LEN + REQUEST_LEN) as u32).to_ne_bytes());
+    request.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend(((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend(1_u32.to_ne_bytes());
+    request.extend(0_u32.to_ne_bytes());
+    // struct inet_diag_req_v2, matching any socket id
+    request.extend([family, ip_protocol as u8, 0, 0]);
+    request.extend(u32::MAX.to_ne_bytes());
+    request.resize(HEADER_LEN + REQUEST_LEN, 0);
+
+    // SAFETY: an all-zero sockaddr_nl is valid, and addresses the kernel
+    let mut kernel: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    // SAFETY: the buffer and the address outlive the call, which is given their sizes
+    let sent = unsafe {
+        libc::sendto(
+            socket.as_raw_fd(),
+            request.as_ptr().cast(),
+            request.len(),
+            0,
+            (&kernel as *const libc::sockaddr_nl).cast(),
+            mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Reads the answers to a dump request until the kernel says it is done.
+fn receive_dump(
+    socket: &OwnedFd,
+    protocol: Protocol,
+    entries: &mut Vec<SocketEntry>,
+) -> io::Result<()> {
+    let mut buffer = vec![0_u8; 32 * 1024];
+    loop {
+        // SAFETY: the kernel writes at most the length of the buffer
+        let received = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut messages = &buffer[..received as usize];
+        while messages.len() >= HEADER_LEN {
+            let len = u32::from_ne_bytes(messages[0..4].try_into().unwrap()) as usize;
+            let kind = u16::from_ne_bytes(messages[4..6].try_into().unwrap());
+            if len < HEADER_LEN || len > messages.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "truncated sock_diag message",
+                ));
+            }
+            let payload = &messages[HEADER_LEN..len];
+            match kind as libc::c_int {
+                libc::NLMSG_DONE => return Ok(()),
+                libc::NLMSG_ERROR => {
+                    let code = payload
+                        .get(0..4)
+                        .map(|code| i32::from_ne_bytes(code.try_into().unwrap()))
+                        .unwrap_or(-libc::EIO);
+                    return Err(io::Error::from_raw_os_error(-code));
+                }
+                _ => entries.extend(parse_message(payload, protocol)),
+            }
+            // messages are aligned to 4 bytes
+            messages = &messages[len.next_multiple_of(4).min(messages.len())..];
+        }
+    }
+}
+
+/// Reads a `struct inet_diag_msg`.
+fn parse_message(message: &[u8], protocol: Protocol) -> Option<SocketEntry> {
+    if message.len() < MESSAGE_LEN {
+        return None;
+    }
+    let family = message[0] as libc::c_int;
+    let state = message[1];
+    let port = u16::from_be_bytes([message[4], message[5]]);
+    let source: [u8; 16] = message[8..24].try_into().unwrap();
+    let ip = match family {
+        libc::AF_INET => IpAddr::V4(Ipv4Addr::new(source[0], source[1], source[2], source[3])),
+        libc::AF_INET6 => IpAddr::V6(Ipv6Addr::from(source)),
+        _ => return None,
+    };
+    let inode = u32::from_ne_bytes(message[68..72].try_into().unwrap()) as u64;
+    // sockets in TIME_WAIT have no inode either, but they were closed by a process
+    let kernel = inode == 0 && !(protocol == Protocol::Tcp && state == TCP_TIME_WAIT);
+    Some(SocketEntry {
+        socket: LocalSocket { ip, port, protocol },
+        inode,
+        kernel,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_diag_messages() {
+        let mut message = [0_u8; MESSAGE_LEN];
+        message[0] = libc::AF_INET as u8;
+        message[1] = TCP_TIME_WAIT;
+        message[4..6].copy_from_slice(&443_u16.to_be_bytes());
+        message[8..12].copy_from_slice(&[10, 0, 0, 2]);
+        let entry = parse_message(&message, Protocol::Tcp).unwrap();
+        assert_eq!(
+            entry.socket,
+            LocalSocket {
+                ip: Ipv4Addr::new(10, 0, 0, 2).into(),
+                port: 443,
+                protocol: Protocol::Tcp,
+            }
+        );
+        assert!(!entry.kernel);
+        // a UDP socket without an inode belongs to the kernel
+        message[0] = libc::AF_INET6 as u8;
+        let entry = parse_message(&message, Protocol::Udp).unwrap();
+        assert_eq!(
+            entry.socket.ip,
+            IpAddr::V6(Ipv6Addr::from([
+                10, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+            ]))
+        );
+        assert!(entry.kernel);
+
+        message[68..72].copy_from_slice(&1234_u32.to_ne_bytes());
+        assert_eq!(parse_message(&message, Protocol::Udp).unwrap().inode, 1234);
+        assert!(parse_message(&message[..40], Protocol::Udp).is_none());
+    }
+}
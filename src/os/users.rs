@@ -0,0 +1,63 @@
+use std::{collections::HashMap, env, fs};
+
+const PASSWD_PATH: &str = "/etc/passwd";
+
+/// Returns a map of UIDs to user names, read from `/etc/passwd`.
+///
+/// Users that only exist in a directory service (LDAP, etc.) are not included;
+/// their UIDs are displayed as-is.
+pub fn get_user_names() -> HashMap<u32, String> {
+    fs::read_to_string(PASSWD_PATH)
+        .map(|content| parse_passwd(&content))
+        .unwrap_or_default()
+}
+
+fn parse_passwd(content: &str) -> HashMap<u32, String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+/// Returns the UID of the user who started nethugs, seeing through `sudo`.
+pub fn get_invoking_uid() -> Option<u32> {
+    if let Some(uid) = env::var("SUDO_UID").ok().and_then(|uid| uid.parse().ok()) {
+        return Some(uid);
+    }
+
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    {
+        use std::os::unix::fs::MetadataExt;
+        fs::metadata("/proc/self").ok().map(|meta| meta.uid())
+    }
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = r#"# local users
+root:x:0:0:root:/root:/bin/bash
+daemon:x:1:1:daemon:/usr/sbin:/usr/sbin/nologin
+alice:x:1000:1000:Alice,,,:/home/alice:/bin/zsh
+broken line
+"#;
+
+    #[test]
+    fn passwd_is_parsed() {
+        let users = parse_passwd(PASSWD);
+        assert_eq!(users.len(), 3);
+        assert_eq!(users.get(&0).map(String::as_str), Some("root"));
+        assert_eq!(users.get(&1000).map(String::as_str), Some("alice"));
+    }
+}
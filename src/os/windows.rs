@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use netstat2::*;
 use sysinfo::{Pid, ProcessesToUpdate, System};
@@ -25,7 +25,10 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
                 .associated_pids
                 .into_iter()
                 .find_map(|pid| sysinfo.process(Pid::from_u32(pid)))
-                .map(|p| ProcessInfo::new(&p.name().to_string_lossy(), p.pid().as_u32()))
+                .map(|p| {
+                    ProcessInfo::new(&p.name().to_string_lossy(), p.pid().as_u32())
+                        .with_start_time(p.start_time())
+                })
                 .unwrap_or_default();
 
             match si.protocol_socket_info {
@@ -55,5 +58,8 @@ pub(crate) fn get_open_sockets() -> OpenSockets {
 
     OpenSockets {
         sockets_to_procs: open_sockets,
+        netns_sockets: BTreeMap::new(),
+        orphan_sockets: HashMap::new(),
+        processes: HashMap::new(),
     }
 }
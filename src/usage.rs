@@ -0,0 +1,351 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use eyre::{eyre, WrapErr};
+use rusqlite::{params, Connection};
+use strum::IntoEnumIterator;
+
+use crate::{
+    cli::{Opt, ReportOpts, Rollup, UsageKind},
+    display::{DisplayBandwidth, NetworkData},
+};
+
+/// How often recorded traffic is written to the database.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS usage (
+        rollup TEXT NOT NULL,
+        period TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        name TEXT NOT NULL,
+        downloaded INTEGER NOT NULL,
+        uploaded INTEGER NOT NULL,
+        PRIMARY KEY (rollup, period, kind, name)
+    );
+";
+
+/// What traffic is accounted to.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UsageKey {
+    pub kind: UsageKind,
+    pub name: String,
+}
+
+/// Traffic of a process or unit within a period.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UsageRow {
+    pub period: String,
+    pub kind: UsageKind,
+    pub name: String,
+    pub downloaded: u64,
+    pub uploaded: u64,
+}
+
+impl Rollup {
+    /// Names the period a moment falls in. Labels of a rollup sort chronologically.
+    fn period(self, at: DateTime<Local>) -> String {
+        let format = match self {
+            Rollup::Hour => "%Y-%m-%d %H:00",
+            Rollup::Day => "%Y-%m-%d",
+            Rollup::Month => "%Y-%m",
+        };
+        at.format(format).to_string()
+    }
+
+    /// How long the periods of this rollup are kept, if not forever.
+    fn retention(self) -> Option<chrono::Duration> {
+        match self {
+            Rollup::Hour => Some(chrono::Duration::days(31)),
+            Rollup::Day => Some(chrono::Duration::days(2 * 366)),
+            Rollup::Month => None,
+        }
+    }
+}
+
+/// The name of a value as given on the command line, which is also how it is stored.
+fn value_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Traffic totals kept across runs, in hourly, daily and monthly rollups.
+pub struct UsageStore {
+    connection: Connection,
+}
+
+impl UsageStore {
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("nethugs").join("usage.sqlite"))
+    }
+
+    /// Opens the database at `path`, or at the default location, creating it if needed.
+    pub fn open(path: Option<&Path>) -> eyre::Result<Self> {
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(UsageStore::path)
+            .ok_or_else(|| eyre!("Cannot find the data directory, use --db instead"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).wrap_err_with(|| format!("Cannot create {}", dir.display()))?;
+        }
+        let connection = Connection::open(&path)
+            .wrap_err_with(|| format!("Cannot open the usage database {}", path.display()))?;
+        UsageStore::with_connection(connection)
+    }
+
+    fn with_connection(connection: Connection) -> eyre::Result<Self> {
+        connection
+            .execute_batch(SCHEMA)
+            .wrap_err("Cannot create the usage database")?;
+        Ok(UsageStore { connection })
+    }
+
+    /// Adds traffic to the periods `at` falls in, and drops periods past their retention.
+    pub fn record(
+        &mut self,
+        at: DateTime<Local>,
+        usage: &HashMap<UsageKey, NetworkData>,
+    ) -> eyre::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut upsert = transaction.prepare_cached(
+                "INSERT INTO usage (rollup, period, kind, name, downloaded, uploaded)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (rollup, period, kind, name) DO UPDATE SET
+                     downloaded = downloaded + excluded.downloaded,
+                     uploaded = uploaded + excluded.uploaded",
+            )?;
+            for rollup in Rollup::iter() {
+                let rollup_name = value_name(rollup);
+                let period = rollup.period(at);
+                for (key, data) in usage {
+                    upsert.execute(params![
+                        rollup_name,
+                        period,
+                        value_name(key.kind),
+                        key.name,
+                        i64::try_from(data.total_bytes_downloaded).unwrap_or(i64::MAX),
+                        i64::try_from(data.total_bytes_uploaded).unwrap_or(i64::MAX),
+                    ])?;
+                }
+                if let Some(retention) = rollup.retention() {
+                    transaction.execute(
+                        "DELETE FROM usage WHERE rollup = ?1 AND period < ?2",
+                        params![rollup_name, rollup.period(at - retention)],
+                    )?;
+                }
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Returns the traffic of every period since `since`, oldest first and busiest first
+    /// within a period.
+    pub fn report(
+        &self,
+        since: DateTime<Local>,
+        rollup: Rollup,
+        kind: Option<UsageKind>,
+    ) -> eyre::Result<Vec<UsageRow>> {
+        let mut query = self.connection.prepare(
+            "SELECT period, kind, name, downloaded, uploaded FROM usage
+             WHERE rollup = ?1 AND period >= ?2 AND (?3 IS NULL OR kind = ?3)
+             ORDER BY period, downloaded + uploaded DESC, name",
+        )?;
+        let rows = query.query_map(
+            params![
+                value_name(rollup),
+                rollup.period(since),
+                kind.map(value_name)
+            ],
+            |row| {
+                let kind: String = row.get(1)?;
+                Ok(UsageRow {
+                    period: row.get(0)?,
+                    kind: UsageKind::from_str(&kind, false).unwrap_or(UsageKind::Process),
+                    name: row.get(2)?,
+                    downloaded: row.get::<_, i64>(3)?.max(0) as u64,
+                    uploaded: row.get::<_, i64>(4)?.max(0) as u64,
+                })
+            },
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Returns the traffic recorded for each process on the day `at` falls in.
+    pub fn today(&self, at: DateTime<Local>) -> eyre::Result<HashMap<String, NetworkData>> {
+        let day = Rollup::Day.period(at);
+        Ok(self
+            .report(at, Rollup::Day, Some(UsageKind::Process))?
+            .into_iter()
+            .filter(|row| row.period == day)
+            .map(|row| {
+                let data = NetworkData {
+                    total_bytes_downloaded: row.downloaded.into(),
+                    total_bytes_uploaded: row.uploaded.into(),
+                };
+                (row.name, data)
+            })
+            .collect())
+    }
+}
+
+/// Prints the recorded traffic for `nethugs report`.
+pub fn print_report(opts: &Opt, report: &ReportOpts) -> eyre::Result<()> {
+    let store = UsageStore::open(opts.db.as_deref())?;
+    let since = Local::now() - report.since;
+    let rows = store.report(since, report.by, report.kind)?;
+    if rows.is_empty() {
+        println!(
+            "No traffic recorded since {}",
+            since.format("%Y-%m-%d %H:%M")
+        );
+        return Ok(());
+    }
+
+    let bytes = |bytes: u64| {
+        DisplayBandwidth {
+            bandwidth: bytes as f64,
+            unit_family: opts.render_opts.unit_family.into(),
+        }
+        .to_string()
+    };
+    let mut totals: HashMap<(UsageKind, &str), (u64, u64)> = HashMap::new();
+    for row in &rows {
+        let total = totals.entry((row.kind, &row.name)).or_default();
+        total.0 += row.downloaded;
+        total.1 += row.uploaded;
+    }
+    let mut totals = totals.into_iter().collect::<Vec<_>>();
+    totals
+        .sort_by(|(a_key, a), (b_key, b)| (b.0 + b.1).cmp(&(a.0 + a.1)).then(a_key.1.cmp(b_key.1)));
+
+    let lines = rows
+        .iter()
+        .map(|row| {
+            (
+                row.period.as_str(),
+                row.kind,
+                row.name.as_str(),
+                row.downloaded,
+                row.uploaded,
+            )
+        })
+        .chain(
+            totals
+                .into_iter()
+                .map(|((kind, name), (downloaded, uploaded))| {
+                    ("total", kind, name, downloaded, uploaded)
+                }),
+        )
+        .map(|(period, kind, name, downloaded, uploaded)| {
+            [
+                period.to_string(),
+                value_name(kind),
+                name.to_string(),
+                bytes(downloaded),
+                bytes(uploaded),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = ["Period", "Kind", "Name", "Down", "Up"].map(String::from);
+    let widths = std::iter::once(&header)
+        .chain(&lines)
+        .fold([0; 5], |mut widths, line| {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+            widths
+        });
+    for line in std::iter::once(&header).chain(&lines) {
+        println!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:>w3$}  {:>w4$}",
+            line[0],
+            line[1],
+            line[2],
+            line[3],
+            line[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn rollups_add_up_and_expire() {
+        let mut store = UsageStore::with_connection(Connection::open_in_memory().unwrap()).unwrap();
+        let at = |day, hour| Local.with_ymd_and_hms(2024, 3, day, hour, 30, 0).unwrap();
+        let usage = |name: &str, downloaded| {
+            HashMap::from([(
+                UsageKey {
+                    kind: UsageKind::Process,
+                    name: name.to_string(),
+                },
+                NetworkData {
+                    total_bytes_downloaded: downloaded,
+                    total_bytes_uploaded: 1,
+                },
+            )])
+        };
+        store.record(at(1, 10), &usage("rsync", 100)).unwrap();
+        store.record(at(1, 11), &usage("rsync", 50)).unwrap();
+        store.record(at(2, 10), &usage("curl", 7)).unwrap();
+
+        let days = store.report(at(1, 0), Rollup::Day, None).unwrap();
+        let days = days
+            .iter()
+            .map(|row| {
+                (
+                    row.period.as_str(),
+                    row.name.as_str(),
+                    row.downloaded,
+                    row.uploaded,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            days,
+            [
+                ("2024-03-01", "rsync", 150, 2),
+                ("2024-03-02", "curl", 7, 1)
+            ]
+        );
+        let today = store.today(at(1, 23)).unwrap();
+        assert_eq!(today.keys().collect::<Vec<_>>(), ["rsync"]);
+        assert_eq!(today["rsync"].total_bytes_downloaded, 150);
+        let months = store.report(at(1, 0), Rollup::Month, None).unwrap();
+        assert_eq!(months.len(), 2);
+        assert!(months.iter().all(|row| row.period == "2024-03"));
+        assert!(store
+            .report(at(1, 0), Rollup::Month, Some(UsageKind::Unit))
+            .unwrap()
+            .is_empty());
+
+        // a month later, the hourly rollup of the first days is gone
+        store
+            .record(at(31, 12) + chrono::Duration::days(5), &usage("curl", 1))
+            .unwrap();
+        let hours = store.report(at(1, 0), Rollup::Hour, None).unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!(store.report(at(1, 0), Rollup::Day, None).unwrap().len(), 3);
+    }
+}